@@ -0,0 +1,495 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::OnceLock,
+};
+
+use acvm::{
+    acir::{circuit::Program, native_types::WitnessStack},
+    AcirField, FieldElement,
+};
+use bn254_blackbox_solver::Bn254BlackBoxSolver;
+use nargo::ops::DefaultForeignCallExecutor;
+use noirc_artifacts::program::ProgramArtifact;
+use noirc_driver::NOIR_ARTIFACT_VERSION_STRING;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::nargo::read_program_from_file;
+
+// Environment variable selecting the proving backend, `bb` unless set to `mock`
+const BACKEND_VAR: &str = "NOIRLINGS_BACKEND";
+
+// Environment variable locating the `bb` binary, over the `bb_path` of the config
+const BB_PATH_VAR: &str = "NOIRLINGS_BB_PATH";
+
+// A bb release, and the Noir release whose circuits it is able to prove
+struct BbRelease {
+    // Noir `major.minor`
+    noir: &'static str,
+    bb: &'static str,
+    // Number of fields given by `vk_as_fields` after the hash of the key, and by
+    // `proof_as_fields` after the public inputs, for a recursive circuit.
+    // The mock backend gives as many, so the recursion exercises take its fields.
+    vk_fields: usize,
+    proof_fields: usize,
+}
+
+// The bb release to use with each Noir release
+const BB_COMPATIBILITY: &[BbRelease] = &[BbRelease {
+    noir: "0.34",
+    bb: "0.55.0",
+    vk_fields: 114,
+    proof_fields: 93,
+}];
+
+/// The files a proving backend reads and writes for an exercise, all in its target directory
+pub struct BackendArtifacts {
+    // The ACIR program artifact, written by nargo
+    pub program: PathBuf,
+    // The solved witness, written by nargo
+    pub witness: PathBuf,
+    pub proof: PathBuf,
+    pub vk: PathBuf,
+    // The verification key and the proof as field elements, in JSON
+    pub vk_fields: PathBuf,
+    pub proof_fields: PathBuf,
+}
+
+impl BackendArtifacts {
+    pub fn new(target_dir: &Path, program_name: &str, exercise_name: &str) -> BackendArtifacts {
+        BackendArtifacts {
+            program: target_dir.join(format!("{program_name}.json")),
+            witness: target_dir.join(format!("{exercise_name}.gz")),
+            proof: target_dir.join(format!("proof-{exercise_name}")),
+            vk: target_dir.join(format!("vk-{exercise_name}")),
+            vk_fields: target_dir.join(format!("vk-{exercise_name}_fields.json")),
+            proof_fields: target_dir.join(format!("proof-{exercise_name}_fields.json")),
+        }
+    }
+}
+
+/// A backend proving the solved circuits of the exercises, mirroring the commands of `bb`
+pub trait ProvingBackend {
+    /// Name shown to the learner
+    fn name(&self) -> &str;
+
+    /// The release of the backend, whose proofs only stand for the same release
+    fn version(&self) -> String;
+
+    /// Creates the proof of the witness
+    fn prove(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()>;
+
+    /// Exports the verification key of the program
+    fn write_vk(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()>;
+
+    /// Verifies the proof with the verification key
+    fn verify(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()>;
+
+    /// Proves and verifies the witness at once, without writing any file
+    fn prove_and_verify(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()>;
+
+    /// Converts the verification key to field elements, as taken by `std::verify_proof`
+    fn vk_as_fields(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()>;
+
+    /// Converts the proof, preceded by its public inputs, to field elements
+    fn proof_as_fields(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()>;
+}
+
+/// Name of the backend selected by `NOIRLINGS_BACKEND`
+pub fn selected_backend() -> String {
+    match env::var(BACKEND_VAR) {
+        Ok(backend) if !backend.is_empty() => backend,
+        _ => "bb".to_string(),
+    }
+}
+
+// The bb found by the detection, kept for the session once found.
+// A failed detection is tried again on the next run, the learner may have installed bb since.
+static BB_DETECTION: OnceLock<BbCli> = OnceLock::new();
+
+/// The backend selected by `NOIRLINGS_BACKEND`
+pub fn proving_backend() -> anyhow::Result<Box<dyn ProvingBackend>> {
+    match selected_backend().as_str() {
+        "bb" => {
+            let bb = match BB_DETECTION.get() {
+                Some(bb) => bb.clone(),
+                None => {
+                    let bb = BbCli::detect()?;
+                    BB_DETECTION.get_or_init(|| bb).clone()
+                }
+            };
+            Ok(Box::new(bb))
+        }
+        "mock" => Ok(Box::new(MockBackend)),
+        other => anyhow::bail!("Unknown {BACKEND_VAR} `{other}`, expected `bb` or `mock`"),
+    }
+}
+
+/// Checks that the selected backend is usable, so a missing or mismatched bb
+/// is reported before the circuit is compiled and executed
+pub fn check_proving_backend() -> anyhow::Result<()> {
+    proving_backend().map(|_| ())
+}
+
+/// Barretenberg, through its `bb` command line
+#[derive(Clone)]
+pub struct BbCli {
+    binary: PathBuf,
+    // As given by `bb --version`
+    version: String,
+}
+
+impl BbCli {
+    pub fn new(binary: PathBuf, version: String) -> BbCli {
+        BbCli { binary, version }
+    }
+
+    /// Locates `bb` and checks that its version matches the Noir version of the runner
+    pub fn detect() -> anyhow::Result<BbCli> {
+        let binary = match env::var_os(BB_PATH_VAR) {
+            Some(path) if !path.is_empty() => PathBuf::from(path),
+            _ => Config::load()
+                .bb_path
+                .unwrap_or_else(|| PathBuf::from("bb")),
+        };
+
+        let output = Command::new(&binary)
+            .arg("--version")
+            .output()
+            .map_err(|err| {
+                anyhow::anyhow!(
+                    "Unable to run `{} --version`: {err}\n\
+                 Install bb following exercises/08_bb_backend/README.md, \
+                 or point {BB_PATH_VAR} to it",
+                    binary.display()
+                )
+            })?;
+        let version = String::from_utf8_lossy(&output.stdout);
+        let version = version
+            .split_whitespace()
+            .last()
+            .unwrap_or_default()
+            .trim_start_matches('v');
+
+        let noir_version = noir_version();
+        let version_name = if version.is_empty() {
+            "of an unknown version"
+        } else {
+            version
+        };
+        match bb_release(noir_version).map(|release| release.bb) {
+            Some(required) if version != required => anyhow::bail!(
+                "Noir {noir_version} needs bb {required}, but `{}` is bb {version_name}\n\
+                 Install it with `bbup -v {required}`",
+                binary.display()
+            ),
+            Some(_) => {}
+            None => warn!(
+                "No bb release is known to work with Noir {}, `{}` is bb {} and may fail to prove",
+                noir_version,
+                binary.display(),
+                version_name
+            ),
+        }
+        Ok(BbCli::new(binary, version.to_string()))
+    }
+
+    fn run(&self, command: &str, args: &[(&str, &Path)], failure: &str) -> anyhow::Result<()> {
+        let mut bb = Command::new(&self.binary);
+        bb.arg(command);
+        for (flag, path) in args {
+            bb.arg(flag).arg(path);
+        }
+        let output = bb.output()?;
+        if !output.status.success() {
+            anyhow::bail!("{failure}: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(())
+    }
+}
+
+impl ProvingBackend for BbCli {
+    fn name(&self) -> &str {
+        "barretenberg (bb)"
+    }
+
+    fn version(&self) -> String {
+        format!("bb {}", self.version)
+    }
+
+    fn prove(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()> {
+        // bb prove -b ./target/hello_world.json -w ./target/witness-name.gz -o ./target/proof-name
+        self.run(
+            "prove",
+            &[
+                ("-b", &artifacts.program),
+                ("-w", &artifacts.witness),
+                ("-o", &artifacts.proof),
+            ],
+            "Failed to prove the program",
+        )
+    }
+
+    fn write_vk(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()> {
+        // bb write_vk -b ./target/hello_world.json -o ./target/vk
+        self.run(
+            "write_vk",
+            &[("-b", &artifacts.program), ("-o", &artifacts.vk)],
+            "Failed to write the verification key",
+        )
+    }
+
+    fn verify(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()> {
+        // bb verify -k ./target/vk -p ./target/proof
+        self.run(
+            "verify",
+            &[("-k", &artifacts.vk), ("-p", &artifacts.proof)],
+            "Failed to verify the program",
+        )
+    }
+
+    fn prove_and_verify(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()> {
+        // more info https://github.com/AztecProtocol/aztec-packages/blob/barretenberg-v0.55.0/barretenberg/cpp/src/barretenberg/bb/main.cpp#L1369-L1512
+        // bb prove_and_verify -b ./target/hello_world.json -w ./target/witness-name.gz
+        self.run(
+            "prove_and_verify",
+            &[("-b", &artifacts.program), ("-w", &artifacts.witness)],
+            "Failed to prove and verify the program",
+        )
+    }
+
+    fn vk_as_fields(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()> {
+        // bb vk_as_fields -k ./target/vk -o ./target/vk_fields.json
+        self.run(
+            "vk_as_fields",
+            &[("-k", &artifacts.vk), ("-o", &artifacts.vk_fields)],
+            "Failed to convert the verification key to fields",
+        )
+    }
+
+    fn proof_as_fields(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()> {
+        // bb proof_as_fields -p ./target/proof -k ./target/vk -o ./target/proof_fields.json
+        self.run(
+            "proof_as_fields",
+            &[
+                ("-p", &artifacts.proof),
+                ("-k", &artifacts.vk),
+                ("-o", &artifacts.proof_fields),
+            ],
+            "Failed to convert the proof to fields",
+        )
+    }
+}
+
+// The version of the Noir compiler of the runner, without its commit hash
+fn noir_version() -> &'static str {
+    NOIR_ARTIFACT_VERSION_STRING
+        .split('+')
+        .next()
+        .unwrap_or(NOIR_ARTIFACT_VERSION_STRING)
+}
+
+// The bb release compatible with a Noir version, if known
+fn bb_release(noir_version: &str) -> Option<&'static BbRelease> {
+    BB_COMPATIBILITY.iter().find(|release| {
+        noir_version
+            .strip_prefix(release.noir)
+            .is_some_and(|patch| patch.is_empty() || patch.starts_with('.'))
+    })
+}
+
+// The bb release the mock backend stands for: the one of the Noir version of the runner,
+// or the latest known
+fn mocked_bb_release() -> &'static BbRelease {
+    bb_release(noir_version()).unwrap_or_else(|| {
+        BB_COMPATIBILITY
+            .last()
+            .expect("At least one bb release is known")
+    })
+}
+
+/// A deterministic stand-in for bb, for CI and machines without it.
+/// Its proofs prove nothing: the witness is checked by solving the ACIR again,
+/// and the "proof" only commits to the circuit and its public inputs.
+pub struct MockBackend;
+
+#[derive(Serialize, Deserialize)]
+struct MockProof {
+    circuit: String,
+    public_inputs: Vec<String>,
+    commitment: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MockVerificationKey {
+    circuit: String,
+}
+
+impl MockBackend {
+    // Checks the witness against the program and returns the proof of it
+    fn create_proof(&self, artifacts: &BackendArtifacts) -> anyhow::Result<MockProof> {
+        let program = read_program_from_file(&artifacts.program)?;
+        let witness_stack =
+            WitnessStack::<FieldElement>::try_from(fs::read(&artifacts.witness)?.as_slice())?;
+        let main_witness = &witness_stack
+            .peek()
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Failed to prove the program: {} holds no witness",
+                    artifacts.witness.display()
+                )
+            })?
+            .witness;
+
+        // Solve the circuit again from the inputs held by the witness
+        let (inputs_map, _) = program.abi.decode(main_witness)?;
+        let initial_witness = program.abi.encode(&inputs_map, None)?;
+        let solved_witness_stack = nargo::ops::execute_program(
+            &program.bytecode,
+            initial_witness,
+            &Bn254BlackBoxSolver,
+            &mut DefaultForeignCallExecutor::new(false, None, None, None),
+        )?;
+        let solved_witness = &solved_witness_stack
+            .peek()
+            .ok_or_else(|| anyhow::anyhow!("Failed to prove the program: no witness was solved"))?
+            .witness;
+        if solved_witness != main_witness {
+            anyhow::bail!("Failed to prove the program: the witness doesn't satisfy the circuit");
+        }
+
+        let main_circuit = &program.bytecode.functions[0];
+        let public_inputs = main_circuit
+            .public_inputs()
+            .0
+            .iter()
+            .map(|witness| {
+                let value = main_witness
+                    .get(witness)
+                    .copied()
+                    .unwrap_or_else(FieldElement::zero);
+                format!("0x{}", value.to_hex())
+            })
+            .collect::<Vec<_>>();
+        let circuit = circuit_hash(&program);
+        Ok(MockProof {
+            commitment: commitment(&circuit, &public_inputs),
+            circuit,
+            public_inputs,
+        })
+    }
+
+    fn check_proof(&self, proof: &MockProof, vk: &MockVerificationKey) -> anyhow::Result<()> {
+        if proof.circuit != vk.circuit
+            || proof.commitment != commitment(&proof.circuit, &proof.public_inputs)
+        {
+            anyhow::bail!(
+                "Failed to verify the program: the proof doesn't match the verification key"
+            );
+        }
+        Ok(())
+    }
+}
+
+impl ProvingBackend for MockBackend {
+    fn name(&self) -> &str {
+        "the mock backend"
+    }
+
+    fn version(&self) -> String {
+        format!("mock of bb {}", mocked_bb_release().bb)
+    }
+
+    fn prove(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()> {
+        let proof = self.create_proof(artifacts)?;
+        write_json(&artifacts.proof, &proof)
+    }
+
+    fn write_vk(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()> {
+        let program = read_program_from_file(&artifacts.program)?;
+        write_json(
+            &artifacts.vk,
+            &MockVerificationKey {
+                circuit: circuit_hash(&program),
+            },
+        )
+    }
+
+    fn verify(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()> {
+        let proof: MockProof = read_json(&artifacts.proof)?;
+        let vk: MockVerificationKey = read_json(&artifacts.vk)?;
+        self.check_proof(&proof, &vk)
+    }
+
+    fn prove_and_verify(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()> {
+        let proof = self.create_proof(artifacts)?;
+        let vk = MockVerificationKey {
+            circuit: proof.circuit.clone(),
+        };
+        self.check_proof(&proof, &vk)
+    }
+
+    fn vk_as_fields(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()> {
+        let vk: MockVerificationKey = read_json(&artifacts.vk)?;
+        // Like bb, the hash of the key comes first
+        let fields = (0..=mocked_bb_release().vk_fields)
+            .map(|index| mock_field(&vk.circuit, index))
+            .collect::<Vec<_>>();
+        write_json(&artifacts.vk_fields, &fields)
+    }
+
+    fn proof_as_fields(&self, artifacts: &BackendArtifacts) -> anyhow::Result<()> {
+        let proof: MockProof = read_json(&artifacts.proof)?;
+        let fields = proof
+            .public_inputs
+            .iter()
+            .cloned()
+            .chain(
+                (0..mocked_bb_release().proof_fields)
+                    .map(|index| mock_field(&proof.commitment, index)),
+            )
+            .collect::<Vec<_>>();
+        write_json(&artifacts.proof_fields, &fields)
+    }
+}
+
+// Identifies the circuit by the hash of its bytecode
+fn circuit_hash(program: &ProgramArtifact) -> String {
+    format!(
+        "{:x}",
+        Sha256::digest(Program::serialize_program(&program.bytecode))
+    )
+}
+
+fn commitment(circuit: &str, public_inputs: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(circuit);
+    for public_input in public_inputs {
+        hasher.update(public_input);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+// A field element derived from the seed, its top byte cleared to stay below the modulus
+fn mock_field(seed: &str, index: usize) -> String {
+    let digest = Sha256::digest(format!("{seed}:{index}"));
+    let hex: String = digest[1..]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("0x00{hex}")
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> anyhow::Result<T> {
+    let content = fs::read(path)
+        .map_err(|err| anyhow::anyhow!("Unable to read {}: {err}", path.display()))?;
+    Ok(serde_json::from_slice(&content)?)
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    fs::write(path, serde_json::to_vec_pretty(value)?)?;
+    Ok(())
+}
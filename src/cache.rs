@@ -0,0 +1,261 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+
+use glob::glob;
+use noirc_driver::NOIR_ARTIFACT_VERSION_STRING;
+
+use crate::backend::{proving_backend, selected_backend};
+use crate::exercise::{CompilerOptions, Exercise, Mode, OracleMock, TomlFile};
+use crate::noir::{copy_dir_all, derived_input_files, exercise_target_dir};
+use crate::output::{output_policy, record_report, report};
+
+// Where the results of the successful runs are stored, one directory per key
+const CACHE_DIR: &str = "target/noirlings/cache";
+
+// File of a cache entry holding the output of the run
+const OUTPUT_FILE: &str = "output.txt";
+
+// File of a cache entry holding what the run reported (solved circuits, test results, ...)
+const REPORT_FILE: &str = "report.txt";
+
+// Directory of a cache entry holding the artifacts of the run (program, witness, proof, ...)
+const ARTIFACTS_DIR: &str = "artifacts";
+
+// Number of entries kept, the least recently used ones are removed beyond it
+const MAX_ENTRIES: usize = 500;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Lets the runs reuse the cached results, as `watch` and `verify` do.
+/// Results are stored whether the cache is enabled or not.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Runs a step of the exercise, unless the same step already succeeded for the same
+/// sources, inputs, settings and compiler version. A reused run reports what the run did,
+/// and restores the files it declared as `outputs` into the target directory of the exercise,
+/// as if the step had just run.
+pub fn cached_run(
+    exercise: &Exercise,
+    step: &str,
+    outputs: &[PathBuf],
+    run: impl FnOnce() -> anyhow::Result<String>,
+) -> anyhow::Result<String> {
+    let key = run_key(exercise, step);
+    if is_enabled() {
+        if let Some((output, run_report)) = restore(exercise, &key) {
+            report(&run_report);
+            return Ok(output);
+        }
+    }
+
+    let (result, run_report) = record_report(run);
+    let output = result?;
+    // Failing to fill the cache only costs a rerun next time
+    let _ = store(exercise, &key, &output, &run_report, outputs);
+    Ok(output)
+}
+
+/// Runs a step of the proving backend like `cached_run`. Its proofs only stand for
+/// the backend, and the release of it, that created them.
+pub fn cached_proving_run(
+    exercise: &Exercise,
+    step: &str,
+    outputs: &[PathBuf],
+    run: impl FnOnce() -> anyhow::Result<String>,
+) -> anyhow::Result<String> {
+    let backend = proving_backend()?;
+    cached_run(
+        exercise,
+        &format!("{step} with {}", backend.version()),
+        outputs,
+        run,
+    )
+}
+
+// The settings of the exercise the result of a step depends on, leaving out the ones
+// only shown to the learner (hints, difficulty, tags, ...)
+#[derive(Serialize)]
+struct RunSettings<'a> {
+    // The artifacts are named after the exercise
+    name: &'a str,
+    mode: &'a Mode,
+    expected_return: &'a Option<toml::Value>,
+    max_opcodes: Option<usize>,
+    max_brillig_opcodes: Option<usize>,
+    compiler: &'a CompilerOptions,
+    dependencies: &'a BTreeMap<String, PathBuf>,
+    oracles: &'a [OracleMock],
+}
+
+impl<'a> RunSettings<'a> {
+    fn new(exercise: &'a Exercise) -> RunSettings<'a> {
+        RunSettings {
+            name: &exercise.name,
+            mode: &exercise.mode,
+            expected_return: &exercise.expected_return,
+            max_opcodes: exercise.max_opcodes,
+            max_brillig_opcodes: exercise.max_brillig_opcodes,
+            compiler: &exercise.compiler,
+            dependencies: &exercise.dependencies,
+            oracles: &exercise.oracles,
+        }
+    }
+}
+
+// Hash of everything the result of the step depends on
+fn run_key(exercise: &Exercise, step: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update(NOIR_ARTIFACT_VERSION_STRING);
+    hasher.update(step);
+    // Proofs of the mock backend mustn't pass for the ones of bb
+    hasher.update(selected_backend());
+    // Covers the mode, the inlined inputs, the expectations and the compiler options
+    hasher.update(
+        serde_json::to_vec(&RunSettings::new(exercise))
+            .expect("The settings of an exercise are serializable"),
+    );
+    // The output and the report of the run hold what the circuit printed according to the policy
+    hasher.update(output_policy(exercise).to_string());
+
+    for file in input_files(exercise) {
+        hasher.update(file.to_string_lossy().as_bytes());
+        match fs::read(&file) {
+            Ok(content) => {
+                hasher.update((content.len() as u64).to_le_bytes());
+                hasher.update(content);
+            }
+            Err(_) => hasher.update("missing"),
+        }
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+// The files the run of the exercise reads, in a stable order
+fn input_files(exercise: &Exercise) -> Vec<PathBuf> {
+    let mut files = exercise.source_files();
+    files.extend(exercise.grader.clone());
+    match &exercise.mode {
+        Mode::Library(options) => files.push(options.consumer.clone()),
+        Mode::BbVerify(options) => files.extend(derived_input_files(exercise, options)),
+        _ => {}
+    }
+    files.extend(exercise.mode.input_files().into_iter().filter_map(|toml_file| match toml_file {
+        TomlFile::Path(path) => Some(path.clone()),
+        TomlFile::Inlined(_) => None,
+    }));
+    for dependency in exercise.dependencies.values() {
+        files.extend(directory_files(dependency));
+    }
+    files
+}
+
+fn directory_files(directory: &Path) -> Vec<PathBuf> {
+    let pattern = directory.join("**").join("*");
+    let mut files: Vec<PathBuf> = glob(&pattern.to_string_lossy())
+        .map(|paths| paths.filter_map(Result::ok).filter(|path| path.is_file()).collect())
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+fn restore(exercise: &Exercise, key: &str) -> Option<(String, String)> {
+    let entry = Path::new(CACHE_DIR).join(key);
+    let output = fs::read_to_string(entry.join(OUTPUT_FILE)).ok()?;
+    let run_report = fs::read_to_string(entry.join(REPORT_FILE)).ok()?;
+    copy_dir_all(&entry.join(ARTIFACTS_DIR), &exercise_target_dir(exercise)).ok()?;
+    // The last use of an entry decides when it gets evicted
+    let _ = OpenOptions::new()
+        .append(true)
+        .open(entry.join(OUTPUT_FILE))
+        .and_then(|file| file.set_modified(SystemTime::now()));
+    Some((output, run_report))
+}
+
+fn store(
+    exercise: &Exercise,
+    key: &str,
+    output: &str,
+    run_report: &str,
+    outputs: &[PathBuf],
+) -> std::io::Result<()> {
+    let entry = Path::new(CACHE_DIR).join(key);
+    if entry.exists() {
+        return Ok(());
+    }
+
+    // Filled aside and renamed, so a cache entry is either complete or missing
+    let partial_entry = Path::new(CACHE_DIR).join(format!("{key}.{}", std::process::id()));
+    let result = fill_entry(exercise, &partial_entry, output, run_report, outputs)
+        .and_then(|_| fs::rename(&partial_entry, &entry));
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&partial_entry);
+        return result;
+    }
+    evict()
+}
+
+// Keeps the files the step declared as its outputs, the other ones belong to other steps.
+// A missing output fails the entry, it couldn't be restored.
+fn fill_entry(
+    exercise: &Exercise,
+    entry: &Path,
+    output: &str,
+    run_report: &str,
+    outputs: &[PathBuf],
+) -> std::io::Result<()> {
+    let artifacts = entry.join(ARTIFACTS_DIR);
+    fs::create_dir_all(&artifacts)?;
+    let target_dir = exercise_target_dir(exercise);
+    for file in outputs {
+        let relative_path = file.strip_prefix(&target_dir).map_err(|_| {
+            std::io::Error::other(format!(
+                "{} is not in the target directory of {exercise}",
+                file.display()
+            ))
+        })?;
+        let cached_file = artifacts.join(relative_path);
+        if let Some(parent) = cached_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(file, cached_file)?;
+    }
+    fs::write(entry.join(REPORT_FILE), run_report)?;
+    fs::write(entry.join(OUTPUT_FILE), output)
+}
+
+// Removes the least recently used entries beyond MAX_ENTRIES
+fn evict() -> std::io::Result<()> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(CACHE_DIR)? {
+        let path = entry?.path();
+        // Entries being filled are left to their run
+        if path.extension().is_some() {
+            continue;
+        }
+        let last_used = fs::metadata(path.join(OUTPUT_FILE))
+            .and_then(|metadata| metadata.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        entries.push((last_used, path));
+    }
+    if entries.len() <= MAX_ENTRIES {
+        return Ok(());
+    }
+
+    entries.sort();
+    for (_, path) in &entries[..entries.len() - MAX_ENTRIES] {
+        fs::remove_dir_all(path)?;
+    }
+    Ok(())
+}
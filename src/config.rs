@@ -0,0 +1,27 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+// Local settings of the learner, next to the revealed hints
+const CONFIG_PATH: &str = ".noirlings/config.toml";
+
+/// Settings read from `.noirlings/config.toml`, all of them optional
+#[derive(Deserialize, Default)]
+pub struct Config {
+    // Location of the `bb` binary, when it isn't on the PATH
+    #[serde(default)]
+    pub bb_path: Option<PathBuf>,
+}
+
+impl Config {
+    /// Loads the config, falling back to the defaults if there is no (valid) config file
+    pub fn load() -> Config {
+        let Ok(content) = fs::read_to_string(CONFIG_PATH) else {
+            return Config::default();
+        };
+        toml::from_str(&content).unwrap_or_else(|err| {
+            println!("Ignoring {CONFIG_PATH}: {err}");
+            Config::default()
+        })
+    }
+}
@@ -1,14 +1,25 @@
 use regex::Regex;
 use serde::de::{self, MapAccess, Visitor};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 
+use std::collections::BTreeMap;
 use std::fmt::{self, Display, Formatter};
 use std::fs::{remove_file, File};
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{self};
 
-use crate::noir::{bb_prove, bb_prove_and_verify, bb_prove_verify_saving_files, nargo_compile, nargo_execute, nargo_test};
+use glob::glob;
+
+use crate::cache::{cached_proving_run, cached_run};
+use crate::nargo::TestFilter;
+use crate::noir::{
+    backend_artifacts, bb_prove, bb_prove_and_verify, bb_prove_verify_saving_files,
+    bb_prover_toml, lock_exercise_workspace, nargo_compile, nargo_compile_fail, nargo_contract,
+    nargo_execute, nargo_execute_cases, nargo_execute_fail, nargo_library, nargo_test,
+    needs_bb_artifacts, WorkspaceLock,
+};
+use crate::output::OutputPolicy;
 
 const I_AM_DONE_REGEX: &str = r"(?m)^\s*///?\s*I\s+AM\s+NOT\s+DONE";
 const CONTEXT: usize = 2;
@@ -25,12 +36,12 @@ fn temp_file() -> String {
 }
 
 // The mode of the exercise.
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum Mode {
     // Indicates that the exercise should be compiled as ACIR
     Build,
-    /** Allow execution with export of witnesses.
+    /** Allow execution with the given witnesses (exported to disk for the bb modes).
     Need to specify the path of the toml file OR the toml content with its input values inlined like
     """
     { execute = {inlined = "a = '1' \nb = '2'"}}
@@ -39,31 +50,244 @@ pub enum Mode {
         """
     { execute = {path = "path/to/toml.toml"}}
     """
+    OR a list of cases, each one executed against the same compiled program
+    """
+    { execute = { cases = [
+        { name = "zero", inputs = {inlined = "x = '0'"}, expected_return = "0" },
+        { name = "overflow", label = "u64 max", inputs = {inlined = "x = '18446744073709551615'"}, expected_failure = "overflow" },
+    ]}}
+    """
     */
     Execute(TomlFile),
+    ExecuteCases(Vec<ExecuteCase>),
     BbProve(TomlFile),
     /**
      *     """
     { bbverify = { toml_file = { path = "path/to/toml.toml"}, save_files = true }
     """
+    Inputs can also be derived from the proof of an exercise it requires, saving its files:
+    """
+    { bbverify = { save_files = true, derived_inputs = {
+        verification_key = { from = "bb_recursion1", artifact = "vk_fields" },
+    }}}
+    """
+    A derived input with `check = true` is given by the learner instead, and checked against it.
      */
     BbVerify(BbVerifyOptions),
     // Indicates that the exercise should be compile and tested from the written Rust-like test
     Test,
+    /** Passes only if the compilation fails with an error containing the expected message.
+    """
+    { compile_fail = { expected = "Expected type u8" } }
+    """
+    */
+    CompileFail(CompileFailOptions),
+    /** Passes only if the execution fails with a failure message containing the expected one.
+    """
+    { execute_fail = { toml_file = {inlined = "x = '1' \ny = '1'"}, expected = "x and y are not equal" } }
+    """
+    */
+    ExecuteFail(ExecuteFailOptions),
+    /** Compiles the exercise as a contract package and checks the functions of the saved artifact.
+    """
+    { contract = { name = "Counter", functions = [
+        { name = "increment", unconstrained = false, parameters = ["counter"], returns = true },
+    ]}}
+    """
+    */
+    Contract(ContractOptions),
+    /** The learner writes a library package, checked by the tests of a hidden consumer package
+    depending on it under the given crate name.
+    """
+    { library = { name = "shapes", consumer = "path/to/consumer.nr" } }
+    """
+    */
+    Library(LibraryOptions),
 }
 
-#[derive(Deserialize,Clone,Debug)]
-pub struct BbVerifyOptions{
+impl Mode {
+    // Whether the mode runs the circuit, or its tests
+    pub fn executes(&self) -> bool {
+        match self {
+            Mode::Execute(_)
+            | Mode::ExecuteCases(_)
+            | Mode::ExecuteFail(_)
+            | Mode::BbProve(_)
+            | Mode::BbVerify(_)
+            | Mode::Test
+            | Mode::Library(_) => true,
+            Mode::Build | Mode::CompileFail(_) | Mode::Contract(_) => false,
+        }
+    }
+
+    // The input files used by the mode
+    pub fn input_files(&self) -> Vec<&TomlFile> {
+        match self {
+            Mode::Execute(toml_file) | Mode::BbProve(toml_file) => vec![toml_file],
+            Mode::BbVerify(options) => vec![&options.toml_file],
+            Mode::ExecuteFail(options) => vec![&options.toml_file],
+            Mode::ExecuteCases(cases) => cases.iter().map(|case| &case.inputs).collect(),
+            Mode::Build
+            | Mode::Test
+            | Mode::CompileFail(_)
+            | Mode::Contract(_)
+            | Mode::Library(_) => Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct BbVerifyOptions {
+    // Can be left out when every input is derived
+    #[serde(default)]
     pub toml_file: TomlFile,
-    pub save_files: bool
+    pub save_files: bool,
+    // Inputs computed from the artifacts of other exercises, by input name
+    #[serde(default)]
+    pub derived_inputs: BTreeMap<String, DerivedInput>,
+}
+
+// An input taken from the proof of another exercise, once converted to fields
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DerivedInput {
+    // The exercise the artifact comes from
+    pub from: String,
+    pub artifact: DerivedArtifact,
+    // Whether the learner gives the input, which is then checked against the derived value,
+    // rather than the runner filling it in
+    #[serde(default)]
+    pub check: bool,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DerivedArtifact {
+    // The verification key as fields, without its hash
+    VkFields,
+    // The hash of the verification key
+    VkHash,
+    // The proof as fields, without its public inputs
+    ProofFields,
+    // The public inputs of the proof
+    PublicInputs,
+}
+
+impl Display for DerivedArtifact {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let description = match self {
+            DerivedArtifact::VkFields => "verification key",
+            DerivedArtifact::VkHash => "hash of the verification key",
+            DerivedArtifact::ProofFields => "proof",
+            DerivedArtifact::PublicInputs => "public inputs",
+        };
+        write!(f, "{description}")
+    }
+}
+
+// A single set of inputs of a table-driven execute exercise.
+// A case without any expectation passes as long as the witness is solved.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ExecuteCase {
+    pub name: String,
+    // A short description displayed next to the case name
+    #[serde(default)]
+    pub label: Option<String>,
+    pub inputs: TomlFile,
+    // The value `main` must return, written as a Prover.toml value
+    #[serde(default)]
+    pub expected_return: Option<toml::Value>,
+    // A substring of the failure message when the case is expected to fail
+    #[serde(default)]
+    pub expected_failure: Option<String>,
+}
+
+// The payload of the `execute` mode: either a single input file or a list of cases
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExecuteInputs {
+    Cases { cases: Vec<ExecuteCase> },
+    Single(TomlFile),
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct CompileFailOptions {
+    // A substring of the compiler diagnostic the learner has to trigger
+    pub expected: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ExecuteFailOptions {
+    pub toml_file: TomlFile,
+    // A substring of the assertion message (or execution error) the learner has to trigger
+    pub expected: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ContractOptions {
+    // Name of the contract the learner has to write
+    pub name: String,
+    // Functions the compiled contract must expose
+    #[serde(default)]
+    pub functions: Vec<ContractFunctionSpec>,
+}
+
+// The expected shape of a contract function, fields left out are not checked
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ContractFunctionSpec {
+    pub name: String,
+    #[serde(default)]
+    pub unconstrained: Option<bool>,
+    // Names of the ABI parameters, in order
+    #[serde(default)]
+    pub parameters: Option<Vec<String>>,
+    // Whether the ABI has a return type
+    #[serde(default)]
+    pub returns: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct LibraryOptions {
+    // The crate name the consumer imports the learner's library with
+    pub name: String,
+    // The path to the consumer sources: a file, or a directory holding a `lib.nr`
+    pub consumer: PathBuf,
+}
+
+// The response of an oracle to a call, e.g.
+// """
+// oracles = [{ name = "get_sqrt", inputs = ["16"], returns = ["4"] }]
+// """
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct OracleMock {
+    pub name: String,
+    // The inputs the mock answers to, any inputs when left out
+    #[serde(default)]
+    pub inputs: Option<Vec<OracleValue>>,
+    pub returns: Vec<OracleValue>,
+}
+
+// A field (decimal or hex) or an array of fields, as passed to and returned by oracles
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum OracleValue {
+    Single(String),
+    Array(Vec<String>),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Serialize, Clone, Debug)]
 pub enum TomlFile {
     Inlined(String),
     Path(String)
 }
 
+impl Default for TomlFile {
+    fn default() -> Self {
+        TomlFile::Inlined(String::new())
+    }
+}
+
 impl TomlFile {
     pub fn to_string(&self) -> String {
         match self {
@@ -156,9 +380,9 @@ where
                 .next_key()?
                 .ok_or_else(|| de::Error::custom("missing key"))?;
             match key.as_str() {
-                "execute" => {
-                    let value: TomlFile = map.next_value()?;
-                    Ok(Mode::Execute(value))
+                "execute" => match map.next_value()? {
+                    ExecuteInputs::Single(value) => Ok(Mode::Execute(value)),
+                    ExecuteInputs::Cases { cases } => Ok(Mode::ExecuteCases(cases)),
                 },
                 "bbprove" => {
                     let value : TomlFile = map.next_value()?;
@@ -168,7 +392,34 @@ where
                     let value : BbVerifyOptions = map.next_value()?;
                     Ok(Mode::BbVerify(value))
                 },
-                _ => Err(de::Error::unknown_field(&key, &["execute","bbprove","bbverify"])),
+                "compile_fail" => {
+                    let value: CompileFailOptions = map.next_value()?;
+                    Ok(Mode::CompileFail(value))
+                },
+                "execute_fail" => {
+                    let value: ExecuteFailOptions = map.next_value()?;
+                    Ok(Mode::ExecuteFail(value))
+                },
+                "contract" => {
+                    let value: ContractOptions = map.next_value()?;
+                    Ok(Mode::Contract(value))
+                },
+                "library" => {
+                    let value: LibraryOptions = map.next_value()?;
+                    Ok(Mode::Library(value))
+                },
+                _ => Err(de::Error::unknown_field(
+                    &key,
+                    &[
+                        "execute",
+                        "bbprove",
+                        "bbverify",
+                        "compile_fail",
+                        "execute_fail",
+                        "contract",
+                        "library",
+                    ],
+                )),
             }
         }
     }
@@ -176,24 +427,313 @@ where
     deserializer.deserialize_any(ModeVisitor)
 }
 
+// The ordered hints of an exercise, from a gentle nudge to the full answer
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(from = "HintsRepr")]
+pub struct Hints(Vec<String>);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HintsRepr {
+    Single(String),
+    Levels(Vec<String>),
+}
+
+impl From<HintsRepr> for Hints {
+    fn from(repr: HintsRepr) -> Self {
+        match repr {
+            HintsRepr::Single(hint) if hint.trim().is_empty() => Hints(Vec::new()),
+            HintsRepr::Single(hint) => Hints(vec![hint]),
+            HintsRepr::Levels(hints) => Hints(hints),
+        }
+    }
+}
+
+impl Hints {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.0.iter()
+    }
+}
+
 #[derive(Deserialize)]
 pub struct ExerciseList {
     pub exercises: Vec<Exercise>,
 }
 
+// The raw content of a manifest file, before chapter defaults are applied.
+// An entry of `exercises` can be `{ include = "path/to/info.toml" }`, which is replaced
+// by the exercises of that chapter manifest, in place. A manifest can set `defaults`
+// for all of its exercises.
+#[derive(Deserialize)]
+struct RawManifest {
+    #[serde(default)]
+    defaults: toml::value::Table,
+    #[serde(default)]
+    exercises: Vec<toml::Value>,
+}
+
+/// An entry of a manifest that couldn't be loaded
+pub struct ManifestError {
+    pub manifest: PathBuf,
+    // The line (1-based) of the entry, or of the syntax error, when known
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{line}: {}", self.manifest.display(), self.message),
+            None => write!(f, "{}: {}", self.manifest.display(), self.message),
+        }
+    }
+}
+
+impl ExerciseList {
+    /// Loads the manifest at `path`, expanding the chapter manifests it includes.
+    /// Paths inside included manifests stay relative to the noirlings directory.
+    /// Fails with the first entry that can't be loaded.
+    pub fn load(path: &Path) -> anyhow::Result<ExerciseList> {
+        let (list, errors) = ExerciseList::load_all(path);
+        match errors.into_iter().next() {
+            Some(error) => Err(anyhow::anyhow!("{error}")),
+            None => Ok(list),
+        }
+    }
+
+    /// Loads every exercise that can be loaded, along with the errors of the other entries
+    pub fn load_all(path: &Path) -> (ExerciseList, Vec<ManifestError>) {
+        let mut exercises = Vec::new();
+        let mut errors = Vec::new();
+        if let Err(message) = load_manifest(path, &mut Vec::new(), &mut exercises, &mut errors) {
+            errors.push(ManifestError {
+                manifest: path.to_path_buf(),
+                line: None,
+                message,
+            });
+        }
+        (ExerciseList { exercises }, errors)
+    }
+}
+
+// Loads the exercises of the manifest, and of the manifests it includes, collecting the errors
+// of their entries. Fails if the manifest itself can't be read.
+fn load_manifest(
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    exercises: &mut Vec<Exercise>,
+    errors: &mut Vec<ManifestError>,
+) -> Result<(), String> {
+    if stack.iter().any(|included| included == path) {
+        return Err(format!("{} includes itself", path.display()));
+    }
+
+    let source = std::fs::read_to_string(path)
+        .map_err(|err| format!("Unable to read {}: {err}", path.display()))?;
+    let manifest: RawManifest = match toml::from_str(&source) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            errors.push(ManifestError {
+                manifest: path.to_path_buf(),
+                line: err.line_col().map(|(line, _)| line + 1),
+                message: format!("Failed to parse the manifest: {err}"),
+            });
+            return Ok(());
+        }
+    };
+    let entry_lines = entry_lines(&source);
+
+    stack.push(path.to_path_buf());
+    for (index, raw_exercise) in manifest.exercises.into_iter().enumerate() {
+        let line = entry_lines.get(index).copied();
+        let failure = if let Some(include) = raw_exercise.get("include") {
+            match include.as_str() {
+                Some(include) => load_manifest(Path::new(include), stack, exercises, errors).err(),
+                None => Some("Invalid include: expected a path".to_string()),
+            }
+        } else {
+            let raw_exercise = apply_defaults(raw_exercise, &manifest.defaults);
+            let name = raw_exercise
+                .get("name")
+                .and_then(toml::Value::as_str)
+                .unwrap_or("<unnamed>")
+                .to_string();
+            match raw_exercise.try_into::<Exercise>() {
+                Ok(mut exercise) => {
+                    exercise.manifest = path.to_path_buf();
+                    exercise.manifest_line = line;
+                    exercises.push(exercise);
+                    None
+                }
+                Err(err) => Some(format!("Invalid exercise `{name}`: {err}")),
+            }
+        };
+
+        if let Some(message) = failure {
+            errors.push(ManifestError {
+                manifest: path.to_path_buf(),
+                line,
+                message,
+            });
+        }
+    }
+    stack.pop();
+    Ok(())
+}
+
+// The lines (1-based) of the `[[exercises]]` headers of a manifest, one per entry
+fn entry_lines(source: &str) -> Vec<usize> {
+    let re = Regex::new(r"^\s*\[\[\s*exercises\s*\]\]").unwrap();
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| re.is_match(line))
+        .map(|(index, _)| index + 1)
+        .collect()
+}
+
+// Fills the keys an exercise leaves out with the defaults of its manifest.
+// `tags` are merged and `dependencies` are extended rather than replaced.
+fn apply_defaults(mut exercise: toml::Value, defaults: &toml::value::Table) -> toml::Value {
+    let Some(table) = exercise.as_table_mut() else {
+        return exercise;
+    };
+
+    for (key, default) in defaults {
+        if !table.contains_key(key) {
+            table.insert(key.clone(), default.clone());
+            continue;
+        }
+        match (key.as_str(), table.get_mut(key), default) {
+            ("tags", Some(toml::Value::Array(tags)), toml::Value::Array(default_tags)) => {
+                let mut merged = default_tags.clone();
+                merged.extend(tags.drain(..).filter(|tag| !default_tags.contains(tag)));
+                *tags = merged;
+            }
+            ("dependencies", Some(toml::Value::Table(dependencies)), toml::Value::Table(default_dependencies)) => {
+                for (name, dependency) in default_dependencies {
+                    dependencies
+                        .entry(name.clone())
+                        .or_insert_with(|| dependency.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    exercise
+}
+
 // A representation of a starklings exercise.
 // This is deserialized from the accompanying info.toml file
 #[derive(Deserialize, Debug)]
 pub struct Exercise {
     // Name of the exercise
     pub name: String,
-    // The path to the file containing the exercise's source code
+    // The path to the file containing the exercise's source code.
+    // It can also be a directory holding a `main.nr` and its modules,
+    // which is copied as a whole into the crate's `src` directory.
     pub path: PathBuf,
     // The mode of the exercise (Test/Build)
     #[serde(deserialize_with = "deserialize_mode")]
     pub mode: Mode,
-    // The hint text associated with the exercise
-    pub hint: String,
+    // The hints associated with the exercise, revealed one level at a time.
+    // Either a single string or an ordered list of strings.
+    pub hint: Hints,
+    // The value `main` must return when executed, compared against the decoded ABI output.
+    // Written as a Prover.toml value, e.g. `expected_return = "3"`.
+    // Overrides the `return` key of the Prover.toml when both are present.
+    #[serde(default)]
+    pub expected_return: Option<toml::Value>,
+    // The path to a file of hidden tests, added to the crate as the `grader` module.
+    // Its tests run next to the learner's ones in test mode.
+    #[serde(default)]
+    pub grader: Option<PathBuf>,
+    // Noir libraries the exercise depends on, by name, as paths to vendored packages.
+    // They are written to the `[dependencies]` of the crate's Nargo.toml.
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, PathBuf>,
+    // Maximum number of ACIR opcodes of the compiled program, summed over its circuits
+    #[serde(default)]
+    pub max_opcodes: Option<usize>,
+    // Maximum number of Brillig opcodes of the compiled program, summed over its unconstrained functions
+    #[serde(default)]
+    pub max_brillig_opcodes: Option<usize>,
+    // How hard the exercise is
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    // Topics covered by the exercise, used to slice the curriculum
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Names of the exercises to be done before this one
+    #[serde(default)]
+    pub requires: Vec<String>,
+    // Rough time needed to solve the exercise
+    #[serde(default)]
+    pub estimated_minutes: Option<u32>,
+    // Options passed to the Noir compiler for every run of the exercise
+    #[serde(default)]
+    pub compiler: CompilerOptions,
+    // Responses of the `#[oracle]` functions the exercise calls, served on localhost while it runs
+    #[serde(default)]
+    pub oracles: Vec<OracleMock>,
+    // What becomes of the output printed by its circuit and tests, unless chosen for the session
+    #[serde(default)]
+    pub output: Option<OutputPolicy>,
+    // The manifest file declaring the exercise, set when loading the manifests
+    #[serde(skip)]
+    pub manifest: PathBuf,
+    // The line of its entry in the manifest, if it's written as an `[[exercises]]` table
+    #[serde(skip)]
+    pub manifest_line: Option<usize>,
+}
+
+// Compiler settings of an exercise, mirroring the `CompileOptions` of nargo.
+// Written as an inline table, e.g. `compiler = { deny_warnings = true }`.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CompilerOptions {
+    // Fail the compilation on any warning
+    #[serde(default)]
+    pub deny_warnings: bool,
+    // Don't report warnings at all
+    #[serde(default)]
+    pub silence_warnings: bool,
+    // Compile the whole program to Brillig
+    #[serde(default)]
+    pub force_brillig: bool,
+    // Skip the search for under-constrained values in unconstrained calls
+    #[serde(default)]
+    pub skip_underconstrained_check: bool,
+    // Width of the ACIR expressions, 0 leaves them unbounded
+    #[serde(default)]
+    pub expression_width: Option<usize>,
+}
+
+// The difficulty level of an exercise
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    Advanced,
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Difficulty::Beginner => write!(f, "beginner"),
+            Difficulty::Intermediate => write!(f, "intermediate"),
+            Difficulty::Advanced => write!(f, "advanced"),
+        }
+    }
 }
 
 // An enum to track of the state of an Exercise.
@@ -236,47 +776,123 @@ impl Drop for FileHandle {
 
 impl Exercise {
     pub fn build(&self) -> anyhow::Result<String> {
-        nargo_compile(&self.path)
+        cached_run(self, "build", &[], || nargo_compile(self))
     }
 
     pub fn execute(&self, prover_toml: TomlFile) -> anyhow::Result<String> {
-        nargo_execute(&self.path, prover_toml, self.name.clone())
+        // The program and the witness are left for the proving backend
+        let outputs = if needs_bb_artifacts(self) {
+            let artifacts = backend_artifacts(self);
+            vec![artifacts.program, artifacts.witness]
+        } else {
+            Vec::new()
+        };
+        cached_run(self, "execute", &outputs, || nargo_execute(self, prover_toml))
+    }
+
+    pub fn execute_cases(&self, cases: &[ExecuteCase]) -> anyhow::Result<String> {
+        cached_run(self, "execute_cases", &[], || nargo_execute_cases(self, cases))
+    }
+
+    pub fn compile_fail(&self, expected: &str) -> anyhow::Result<String> {
+        cached_run(self, "compile_fail", &[], || nargo_compile_fail(self, expected))
+    }
+
+    pub fn execute_fail(&self, prover_toml: TomlFile, expected: &str) -> anyhow::Result<String> {
+        cached_run(self, "execute_fail", &[], || {
+            nargo_execute_fail(self, prover_toml, expected)
+        })
+    }
+
+    pub fn compile_contract(&self, options: &ContractOptions) -> anyhow::Result<String> {
+        cached_run(self, "contract", &[], || nargo_contract(self, options))
+    }
+
+    pub fn test_library(&self) -> anyhow::Result<String> {
+        cached_run(self, "library", &[], || nargo_library(self))
+    }
+
+    // The inputs of a bb exercise, including the ones derived from the exercises it requires
+    pub fn bb_prover_toml(&self, options: &BbVerifyOptions) -> anyhow::Result<TomlFile> {
+        bb_prover_toml(self, options)
     }
 
     pub fn create_proof(&self) -> anyhow::Result<String> {
-        bb_prove(self.name.clone())
+        let proof = backend_artifacts(self).proof;
+        cached_proving_run(self, "prove", &[proof], || bb_prove(self))
     }
 
     pub fn prove_verify_proof(&self, saving_files: bool) -> anyhow::Result<String> {
         if (saving_files) {
-            return bb_prove_verify_saving_files(self.name.clone());
+            let artifacts = backend_artifacts(self);
+            let outputs = [artifacts.proof, artifacts.vk];
+            return cached_proving_run(self, "prove_verify_saving_files", &outputs, || {
+                bb_prove_verify_saving_files(self)
+            });
         } else {
-            return bb_prove_and_verify(self.name.clone());
+            return cached_proving_run(self, "prove_verify", &[], || bb_prove_and_verify(self));
         }
     }
 
+    // Locks the generated workspace of the exercise for the duration of a run
+    pub fn lock_workspace(&self) -> anyhow::Result<WorkspaceLock> {
+        lock_exercise_workspace(self)
+    }
+
     pub fn test(&self) -> anyhow::Result<String> {
-        nargo_test(&self.path)
+        self.test_matching(&TestFilter::Anything)
+    }
+
+    // Runs only the test functions selected by the filter
+    pub fn test_matching(&self, filter: &TestFilter) -> anyhow::Result<String> {
+        cached_run(self, &format!("test {filter:?}"), &[], || {
+            nargo_test(self, filter)
+        })
+    }
+
+    // Whether the exercise is made of a directory of Noir modules rather than a single file
+    pub fn is_multi_file(&self) -> bool {
+        self.path.is_dir()
+    }
+
+    // The Noir source files making up the exercise, in a stable order
+    pub fn source_files(&self) -> Vec<PathBuf> {
+        if !self.is_multi_file() {
+            return vec![self.path.clone()];
+        }
+
+        let pattern = self.path.join("**").join("*.nr");
+        let mut files: Vec<PathBuf> = glob(&pattern.to_string_lossy())
+            .expect("Invalid exercise path")
+            .filter_map(Result::ok)
+            .collect();
+        files.sort();
+        files
+    }
+
+    // Whether the given file belongs to this exercise
+    pub fn owns_file(&self, file: &Path) -> bool {
+        file.ancestors().any(|ancestor| ancestor.ends_with(&self.path))
     }
 
     pub fn state(&self) -> State {
-        let mut source_file = File::open(&self.path).unwrap_or_else(|_| {
-            panic!("We were unable to open the exercise file! {:?}", self.path)
-        });
+        let re = Regex::new(I_AM_DONE_REGEX).unwrap();
+
+        let pending_source = self.source_files().into_iter().find_map(|file| {
+            let mut source_file = File::open(&file).unwrap_or_else(|_| {
+                panic!("We were unable to open the exercise file! {:?}", file)
+            });
 
-        let source = {
-            let mut s = String::new();
+            let mut source = String::new();
             source_file
-                .read_to_string(&mut s)
+                .read_to_string(&mut source)
                 .expect("We were unable to read the exercise file!");
-            s
-        };
-
-        let re = Regex::new(I_AM_DONE_REGEX).unwrap();
+            re.is_match(&source).then_some(source)
+        });
 
-        if !re.is_match(&source) {
+        let Some(source) = pending_source else {
             return State::Done;
-        }
+        };
 
         let matched_line_index = source
             .lines()
@@ -318,6 +934,18 @@ impl Display for Exercise {
     }
 }
 
+// The first pending exercise whose prerequisites are all done,
+// falling back to file order if prerequisites can't be satisfied
+pub fn next_exercise(exercises: &[Exercise]) -> Option<&Exercise> {
+    let pending: Vec<&Exercise> = exercises.iter().filter(|e| !e.looks_done()).collect();
+    let is_pending = |required: &String| pending.iter().any(|e| &e.name == required);
+    pending
+        .iter()
+        .find(|e| !e.requires.iter().any(is_pending))
+        .or_else(|| pending.first())
+        .copied()
+}
+
 #[inline]
 fn clean() {
     let _ignored = remove_file(temp_file());
@@ -346,9 +974,366 @@ mod test {
             name: "testPass".into(),
             path: PathBuf::from("tests/fixture/noir/testPass.nr"),
             mode: Mode::Test,
-            hint: String::new(),
+            hint: Hints::default(),
+            expected_return: None,
+            grader: None,
+            dependencies: BTreeMap::new(),
+            max_opcodes: None,
+            max_brillig_opcodes: None,
+            difficulty: None,
+            tags: Vec::new(),
+            requires: Vec::new(),
+            estimated_minutes: None,
+            compiler: CompilerOptions::default(),
+            oracles: Vec::new(),
+            output: None,
+            manifest: PathBuf::new(),
+            manifest_line: None,
         };
 
         assert_eq!(exercise.state(), State::Done);
     }
+
+    // An exercise of the given fixture, requiring the given exercises
+    fn fixture_exercise(name: &str, fixture: &str, requires: &[&str]) -> Exercise {
+        toml::from_str(&format!(
+            r#"
+            name = "{name}"
+            path = "tests/fixture/noir/{fixture}"
+            mode = "build"
+            hint = ""
+            requires = {requires:?}
+            "#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_chapter_defaults_are_applied() {
+        let defaults = toml::from_str::<toml::Value>(
+            r#"
+            mode = "test"
+            difficulty = "beginner"
+            tags = ["hashmaps", "collections"]
+            dependencies = { helpers = "vendor/helpers", bignum = "vendor/bignum" }
+            "#,
+        )
+        .unwrap();
+        let exercise = toml::from_str::<toml::Value>(
+            r#"
+            name = "hashmaps1"
+            path = "exercises/07_hash_maps/hashmaps1.nr"
+            difficulty = "advanced"
+            tags = ["collections", "generics"]
+            dependencies = { bignum = "vendor/bignum_v2", sort = "vendor/sort" }
+            hint = ""
+            "#,
+        )
+        .unwrap();
+
+        let exercise: Exercise = apply_defaults(exercise, defaults.as_table().unwrap())
+            .try_into()
+            .unwrap();
+
+        // Keys left out take the default, the ones set override it
+        assert!(matches!(exercise.mode, Mode::Test));
+        assert_eq!(exercise.difficulty, Some(Difficulty::Advanced));
+        // Tags are merged, the default ones first and without duplicates
+        assert_eq!(exercise.tags, vec!["hashmaps", "collections", "generics"]);
+        // Dependencies are merged, the ones of the exercise win
+        assert_eq!(
+            exercise.dependencies,
+            BTreeMap::from([
+                ("bignum".to_string(), PathBuf::from("vendor/bignum_v2")),
+                ("helpers".to_string(), PathBuf::from("vendor/helpers")),
+                ("sort".to_string(), PathBuf::from("vendor/sort")),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_included_exercises_keep_their_place() {
+        let dir = std::env::temp_dir().join(format!("noirlings_include_{}", process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let chapter = dir.join("chapter.toml");
+        std::fs::write(
+            &chapter,
+            r#"
+            defaults = { mode = "test" }
+
+            [[exercises]]
+            name = "second"
+            path = "exercises/second.nr"
+            hint = ""
+
+            [[exercises]]
+            name = "third"
+            path = "exercises/third.nr"
+            hint = ""
+            "#,
+        )
+        .unwrap();
+        let manifest = dir.join("info.toml");
+        std::fs::write(
+            &manifest,
+            format!(
+                r#"
+                [[exercises]]
+                name = "first"
+                path = "exercises/first.nr"
+                mode = "build"
+                hint = ""
+
+                [[exercises]]
+                include = '{}'
+
+                [[exercises]]
+                name = "fourth"
+                path = "exercises/fourth.nr"
+                mode = "build"
+                hint = ""
+                "#,
+                chapter.display()
+            ),
+        )
+        .unwrap();
+
+        let list = ExerciseList::load(&manifest);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let list = list.unwrap();
+        let names: Vec<&str> = list.exercises.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["first", "second", "third", "fourth"]);
+        // The defaults of a chapter only apply to its own exercises
+        assert!(matches!(list.exercises[1].mode, Mode::Test));
+        assert!(matches!(list.exercises[3].mode, Mode::Build));
+        assert_eq!(list.exercises[2].manifest, chapter);
+    }
+
+    #[test]
+    fn test_hint_levels_are_parsed() {
+        let list = toml::from_str::<ExerciseList>(
+            r#"
+            [[exercises]]
+            name = "single"
+            path = "tests/fixture/noir/testPass.nr"
+            mode = "test"
+            hint = "The only hint"
+
+            [[exercises]]
+            name = "levels"
+            path = "tests/fixture/noir/testPass.nr"
+            mode = "test"
+            hint = ["A nudge", "The answer"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(list.exercises[0].hint.len(), 1);
+        assert_eq!(
+            list.exercises[1].hint.iter().collect::<Vec<_>>(),
+            vec!["A nudge", "The answer"]
+        );
+    }
+
+    #[test]
+    fn test_expected_failure_modes_are_parsed() {
+        let list = toml::from_str::<ExerciseList>(
+            r#"
+            [[exercises]]
+            name = "compileFail"
+            path = "tests/fixture/noir/testFails.nr"
+            mode = { compile_fail = { expected = "Expected type u8" } }
+            hint = ""
+
+            [[exercises]]
+            name = "executeFail"
+            path = "tests/fixture/noir/testFails.nr"
+            mode = { execute_fail = { toml_file = { inlined = "x = '1'" }, expected = "not equal" } }
+            hint = ""
+            "#,
+        )
+        .unwrap();
+
+        assert!(
+            matches!(&list.exercises[0].mode, Mode::CompileFail(options) if options.expected == "Expected type u8")
+        );
+        assert!(
+            matches!(&list.exercises[1].mode, Mode::ExecuteFail(options) if options.expected == "not equal")
+        );
+    }
+
+    #[test]
+    fn test_execute_cases_are_parsed() {
+        let list = toml::from_str::<ExerciseList>(
+            r#"
+            [[exercises]]
+            name = "single"
+            path = "tests/fixture/noir/testPass.nr"
+            mode = { execute = { inlined = "x = '1'" } }
+            hint = ""
+
+            [[exercises]]
+            name = "cases"
+            path = "tests/fixture/noir/testPass.nr"
+            mode = { execute = { cases = [
+                { name = "zero", inputs = { inlined = "x = '0'" }, expected_return = "0" },
+                { name = "equal", label = "x == y", inputs = { path = "equal.toml" }, expected_failure = "x != y" },
+            ] } }
+            hint = ""
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(list.exercises[0].mode, Mode::Execute(TomlFile::Inlined(_))));
+        let Mode::ExecuteCases(cases) = &list.exercises[1].mode else {
+            panic!("Expected a table-driven execute mode");
+        };
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[1].label.as_deref(), Some("x == y"));
+        assert_eq!(cases[1].expected_failure.as_deref(), Some("x != y"));
+    }
+
+    #[test]
+    fn test_compiler_options_are_parsed() {
+        let list = toml::from_str::<ExerciseList>(
+            r#"
+            [[exercises]]
+            name = "defaults"
+            path = "tests/fixture/noir/testPass.nr"
+            mode = "build"
+            hint = ""
+
+            [[exercises]]
+            name = "strict"
+            path = "tests/fixture/noir/testPass.nr"
+            mode = "build"
+            hint = ""
+            compiler = { deny_warnings = true, force_brillig = true, expression_width = 4 }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(list.exercises[0].compiler, CompilerOptions::default());
+        let strict = &list.exercises[1].compiler;
+        assert!(strict.deny_warnings && strict.force_brillig);
+        assert!(!strict.silence_warnings);
+        assert_eq!(strict.expression_width, Some(4));
+    }
+
+    #[test]
+    fn test_derived_inputs_are_parsed() {
+        let list = toml::from_str::<ExerciseList>(
+            r#"
+            [[exercises]]
+            name = "recursion"
+            path = "tests/fixture/noir/testPass.nr"
+            hint = ""
+            requires = ["inner"]
+
+            [exercises.mode.bbverify]
+            save_files = true
+
+            [exercises.mode.bbverify.derived_inputs]
+            key_hash = { from = "inner", artifact = "vk_hash" }
+            proof = { from = "inner", artifact = "proof_fields" }
+            public_inputs = { from = "inner", artifact = "public_inputs", check = true }
+            "#,
+        )
+        .unwrap();
+
+        let Mode::BbVerify(options) = &list.exercises[0].mode else {
+            panic!("Expected a bbverify mode");
+        };
+        assert!(matches!(&options.toml_file, TomlFile::Inlined(inputs) if inputs.is_empty()));
+        assert_eq!(
+            options.derived_inputs["key_hash"],
+            DerivedInput {
+                from: "inner".to_string(),
+                artifact: DerivedArtifact::VkHash,
+                check: false,
+            }
+        );
+        assert_eq!(
+            options.derived_inputs["proof"].artifact,
+            DerivedArtifact::ProofFields
+        );
+        assert!(options.derived_inputs["public_inputs"].check);
+    }
+
+    #[test]
+    fn test_oracle_mocks_are_parsed() {
+        let list = toml::from_str::<ExerciseList>(
+            r#"
+            [[exercises]]
+            name = "oracle"
+            path = "tests/fixture/noir/testPass.nr"
+            mode = "test"
+            hint = ""
+            oracles = [
+                { name = "get_sqrt", inputs = ["16"], returns = ["4"] },
+                { name = "get_pair", returns = [["1", "0x02"]] },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        let oracles = &list.exercises[0].oracles;
+        assert_eq!(
+            oracles[0].inputs,
+            Some(vec![OracleValue::Single("16".to_string())])
+        );
+        assert_eq!(oracles[1].inputs, None);
+        assert_eq!(
+            oracles[1].returns,
+            vec![OracleValue::Array(vec![
+                "1".to_string(),
+                "0x02".to_string()
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_output_policy_is_parsed() {
+        let list = toml::from_str::<ExerciseList>(
+            r#"
+            [[exercises]]
+            name = "quiet"
+            path = "tests/fixture/noir/testPass.nr"
+            mode = "test"
+            hint = ""
+            output = "suppress"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(list.exercises[0].output, Some(OutputPolicy::Suppress));
+        assert_eq!("show".parse::<OutputPolicy>(), Ok(OutputPolicy::Show));
+        assert!("loud".parse::<OutputPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_next_exercise_waits_for_its_requirements() {
+        let exercises = vec![
+            fixture_exercise("done", "testPass.nr", &[]),
+            fixture_exercise("advanced", "notDone.nr", &["basics"]),
+            fixture_exercise("basics", "notDone.nr", &["done"]),
+        ];
+
+        let next = next_exercise(&exercises).unwrap();
+
+        assert_eq!(next.name, "basics");
+    }
+
+    #[test]
+    fn test_next_exercise_falls_back_to_file_order() {
+        let exercises = vec![
+            fixture_exercise("done", "testPass.nr", &[]),
+            fixture_exercise("chicken", "notDone.nr", &["egg"]),
+            fixture_exercise("egg", "notDone.nr", &["chicken"]),
+        ];
+
+        assert_eq!(next_exercise(&exercises).unwrap().name, "chicken");
+        assert!(next_exercise(&exercises[..1]).is_none());
+    }
 }
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::exercise::Hints;
+
+// Where the number of hint levels revealed per exercise is stored
+const REVEALED_HINTS_PATH: &str = ".noirlings/hints.json";
+
+/// Number of hint levels revealed so far for each exercise, stored locally
+/// so that `hint` keeps revealing from where the learner stopped.
+#[derive(Serialize, Deserialize, Default)]
+pub struct RevealedHints {
+    levels: BTreeMap<String, usize>,
+}
+
+impl RevealedHints {
+    /// Loads the revealed hints, starting from scratch if there is no (valid) state yet
+    pub fn load() -> RevealedHints {
+        fs::read(REVEALED_HINTS_PATH)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the revealed hints to disk
+    pub fn save(&self) -> Result<(), std::io::Error> {
+        if let Some(parent) = Path::new(REVEALED_HINTS_PATH).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(
+            REVEALED_HINTS_PATH,
+            serde_json::to_vec(&self).expect("Failed to serialize to JSON"),
+        )
+    }
+
+    /// Number of hint levels revealed for the given exercise
+    pub fn level(&self, exercise_name: &str) -> usize {
+        self.levels.get(exercise_name).copied().unwrap_or(0)
+    }
+
+    /// Reveals one more level of the hints, returning the new level
+    pub fn reveal_next(&mut self, exercise_name: &str, hints: &Hints) -> usize {
+        let level = (self.level(exercise_name) + 1).min(hints.len());
+        self.levels.insert(exercise_name.to_string(), level);
+        level
+    }
+}
+
+/// Reveals the next hint level of an exercise and returns the text of every revealed level
+pub fn reveal_hint(exercise_name: &str, hints: &Hints) -> String {
+    if hints.is_empty() {
+        return "There is no hint for this exercise.".to_string();
+    }
+
+    let mut revealed = RevealedHints::load();
+    let level = revealed.reveal_next(exercise_name, hints);
+    if revealed.save().is_err() {
+        println!("Failed to save the revealed hints to {REVEALED_HINTS_PATH}");
+    }
+
+    let mut text = hints
+        .iter()
+        .take(level)
+        .enumerate()
+        .map(|(index, hint)| format!("Hint {}/{}:\n{}", index + 1, hints.len(), hint.trim_end()))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    if level < hints.len() {
+        text.push_str("\n\nAsk again for the next hint.");
+    }
+    text
+}
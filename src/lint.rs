@@ -0,0 +1,169 @@
+use glob::glob;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::exercise::{Exercise, ExerciseList, Mode, State, TomlFile};
+
+// Example exercises for contributors are only referenced by commented out entries
+const EXAMPLES_DIR: &str = "exercises/examples_for_contributors";
+
+// A problem found in the manifests or the exercise tree
+struct Problem {
+    // The manifest and line the problem relates to, if any
+    location: Option<(PathBuf, Option<usize>)>,
+    message: String,
+}
+
+/// Loads the exercise list from `manifest_path` (and the manifests it includes) and
+/// reports every problem found in them and in the `exercises` directory.
+/// Entries that can't be loaded are reported, the other ones are still checked.
+/// Returns the number of problems.
+pub fn lint_info(manifest_path: &str) -> usize {
+    let (list, errors) = ExerciseList::load_all(Path::new(manifest_path));
+    let exercises = list.exercises;
+
+    let mut problems: Vec<Problem> = errors
+        .into_iter()
+        .map(|error| Problem {
+            location: Some((error.manifest, error.line)),
+            message: error.message,
+        })
+        .collect();
+    problems.extend(lint_exercises(&exercises));
+    for problem in &problems {
+        match &problem.location {
+            Some((manifest, Some(line))) => {
+                println!("{}:{line}: {}", manifest.display(), problem.message)
+            }
+            Some((manifest, None)) => println!("{}: {}", manifest.display(), problem.message),
+            None => println!("{}", problem.message),
+        }
+    }
+
+    if problems.is_empty() {
+        println!("{manifest_path}: {} exercises, no problems found", exercises.len());
+    } else {
+        let plural = if problems.len() == 1 { "" } else { "s" };
+        println!("{manifest_path}: {} problem{plural} found", problems.len());
+    }
+    problems.len()
+}
+
+fn lint_exercises(exercises: &[Exercise]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut referenced: BTreeSet<PathBuf> = BTreeSet::new();
+
+    let by_name: HashMap<&str, &Exercise> = exercises
+        .iter()
+        .map(|exercise| (exercise.name.as_str(), exercise))
+        .collect();
+
+    let mut occurrences: HashMap<&str, usize> = HashMap::new();
+    for exercise in exercises {
+        let count = occurrences.entry(exercise.name.as_str()).or_default();
+        *count += 1;
+
+        let location = Some((exercise.manifest.clone(), exercise.manifest_line));
+        let mut report = |message: String| {
+            problems.push(Problem {
+                location: location.clone(),
+                message,
+            })
+        };
+
+        if *count > 1 {
+            report(format!("duplicate exercise name `{}`", exercise.name));
+        }
+
+        if !exercise.path.exists() {
+            report(format!(
+                "`{}`: exercise file {} doesn't exist",
+                exercise.name,
+                exercise.path.display()
+            ));
+        } else {
+            for file in exercise.source_files() {
+                referenced.insert(file);
+            }
+            if matches!(exercise.state(), State::Done) {
+                report(format!(
+                    "`{}`: no `I AM NOT DONE` marker in {}",
+                    exercise.name,
+                    exercise.path.display()
+                ));
+            }
+        }
+
+        let mut extra_files: Vec<(&str, &Path)> = Vec::new();
+        if let Some(grader) = &exercise.grader {
+            extra_files.push(("grader file", grader));
+        }
+        if let Mode::Library(options) = &exercise.mode {
+            extra_files.push(("consumer", &options.consumer));
+        }
+        for (name, path) in &exercise.dependencies {
+            if !path.join("Nargo.toml").exists() {
+                report(format!(
+                    "`{}`: dependency `{name}` has no Nargo.toml in {}",
+                    exercise.name,
+                    path.display()
+                ));
+            }
+        }
+        for (kind, path) in extra_files {
+            if path.exists() {
+                referenced.insert(path.to_path_buf());
+            } else {
+                report(format!(
+                    "`{}`: {kind} {} doesn't exist",
+                    exercise.name,
+                    path.display()
+                ));
+            }
+        }
+
+        for input in exercise.mode.input_files() {
+            if let TomlFile::Path(path) = input {
+                if !Path::new(path).exists() {
+                    report(format!("`{}`: input file {path} doesn't exist", exercise.name));
+                }
+            }
+        }
+
+        if let Mode::BbVerify(options) = &exercise.mode {
+            for (input, derived_input) in &options.derived_inputs {
+                let from = derived_input.from.as_str();
+                let saves_files = by_name.get(from).is_some_and(|required| {
+                    matches!(&required.mode, Mode::BbVerify(options) if options.save_files)
+                });
+                if !saves_files {
+                    report(format!(
+                        "`{}`: input `{input}` is derived from `{from}`, which isn't a bbverify exercise saving its files",
+                        exercise.name
+                    ));
+                } else if !exercise.requires.iter().any(|required| required == from) {
+                    report(format!(
+                        "`{}`: input `{input}` is derived from `{from}`, which must be in `requires`",
+                        exercise.name
+                    ));
+                }
+            }
+        }
+    }
+
+    for file in glob("exercises/**/*.nr").into_iter().flatten().flatten() {
+        if file.starts_with(EXAMPLES_DIR) {
+            continue;
+        }
+        let is_referenced = referenced.contains(&file)
+            || referenced.iter().any(|path| path.is_dir() && file.starts_with(path));
+        if !is_referenced {
+            problems.push(Problem {
+                location: None,
+                message: format!("{} is not referenced by any exercise", file.display()),
+            });
+        }
+    }
+
+    problems
+}
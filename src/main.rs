@@ -1,14 +1,17 @@
-use crate::exercise::{Exercise, ExerciseList};
+use crate::exercise::{next_exercise, Exercise, ExerciseList, Hints};
+use crate::hints::{reveal_hint, RevealedHints};
+use crate::nargo::TestFilter;
+use crate::noir::failed_test;
+use crate::output::OutputPolicy;
 use crate::project::RustAnalyzerProject;
 use crate::run::{reset, run};
-use crate::verify::verify;
+use crate::verify::{verify, verify_parallel};
 use argh::FromArgs;
 use console::Emoji;
 use core::panic;
 use notify::DebouncedEvent;
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use std::ffi::OsStr;
-use std::fs;
 use std::io::{self, prelude::*};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
@@ -20,9 +23,16 @@ use std::time::Duration;
 
 #[macro_use]
 mod ui;
+mod backend;
+mod cache;
+mod config;
 mod exercise;
+mod hints;
+mod lint;
 mod nargo;
 mod noir;
+mod oracle;
+mod output;
 mod project;
 mod run;
 mod utils;
@@ -34,9 +44,12 @@ const VERSION: &str = "5.3.0";
 #[derive(FromArgs, PartialEq, Debug)]
 /// noirlings is a collection of small exercises to get you used to writing and reading Rust code
 struct Args {
-    /// show outputs from the test exercises
+    /// show outputs from the test exercises, as `--output show`
     #[argh(switch)]
     nocapture: bool,
+    /// what to do with the output of the circuits and tests: show, capture or suppress
+    #[argh(option)]
+    output: Option<OutputPolicy>,
     /// show the executable version
     #[argh(switch, short = 'v')]
     version: bool,
@@ -55,12 +68,17 @@ enum Subcommands {
     List(ListArgs),
     Paths(PathsArgs),
     Lsp(LspArgs),
+    LintInfo(LintInfoArgs),
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "verify")]
 /// Verifies all exercises according to the recommended order
-struct VerifyArgs {}
+struct VerifyArgs {
+    /// number of exercises verified concurrently, reported once they are all done
+    #[argh(option, short = 'j')]
+    jobs: Option<usize>,
+}
 
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "watch")]
@@ -80,6 +98,12 @@ struct RunArgs {
     #[argh(positional)]
     /// the name of the exercise
     name: String,
+    /// only run the test functions of the exercise matching the pattern
+    #[argh(option)]
+    test: Option<String>,
+    /// how --test matches the test names: exact, contains (default) or starts-with
+    #[argh(option, long = "match", default = "String::from(\"contains\")")]
+    matching: String,
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
@@ -93,7 +117,7 @@ struct ResetArgs {
 
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "hint")]
-/// Returns a hint for the given exercise
+/// Reveals the next hint level for the given exercise
 struct HintArgs {
     #[argh(positional)]
     /// the name of the exercise
@@ -105,6 +129,11 @@ struct HintArgs {
 /// Enable rust-analyzer for exercises
 struct LspArgs {}
 
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "lint-info")]
+/// Checks info.toml and the exercises directory for problems
+struct LintInfoArgs {}
+
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "list")]
 /// Lists the exercises available in noirlings
@@ -125,6 +154,14 @@ struct ListArgs {
     #[argh(switch, short = 's')]
     /// display only exercises that have been solved
     solved: bool,
+    #[argh(option)]
+    /// display only exercises with this tag
+    /// comma separated tags are acceptable
+    tag: Option<String>,
+    #[argh(option)]
+    /// display only exercises of this difficulty
+    /// (beginner, intermediate or advanced)
+    difficulty: Option<String>,
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
@@ -135,7 +172,13 @@ struct PathsArgs {}
 fn main() {
     let args: Args = argh::from_env();
 
+    // `verify --jobs` runs its exercises on the global pool
+    let num_threads = match &args.nested {
+        Some(Subcommands::Verify(subargs)) => subargs.jobs.unwrap_or(0),
+        _ => 0,
+    };
     let _ = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
         .stack_size(8 * 1024 * 1024)
         .build_global();
 
@@ -148,6 +191,12 @@ fn main() {
         println!("\n{WELCOME}\n");
     }
 
+    if let Some(policy) = args.output {
+        output::set_session_policy(policy);
+    } else if args.nocapture {
+        output::set_session_policy(OutputPolicy::Show);
+    }
+
     if !Path::new("info.toml").exists() {
         println!(
             "{} must be run from the noirlings directory",
@@ -164,25 +213,55 @@ fn main() {
         std::process::exit(1);
     }
 
-    let toml_str = &fs::read_to_string("info.toml").unwrap();
-    let mut exercises = toml::from_str::<ExerciseList>(toml_str).unwrap().exercises;
     let command = args.nested.unwrap_or_else(|| {
         println!("{DEFAULT_OUT}\n");
         std::process::exit(0);
     });
+
+    // Linting has to work even when the manifest can't be parsed
+    if let Subcommands::LintInfo(_) = command {
+        let problems = lint::lint_info("info.toml");
+        std::process::exit(if problems == 0 { 0 } else { 1 });
+    }
+
+    let mut exercises = match ExerciseList::load(Path::new("info.toml")) {
+        Ok(list) => list.exercises,
+        Err(e) => {
+            println!("{e}");
+            println!("Run `noirlings lint-info` to check it for problems.");
+            std::process::exit(1);
+        }
+    };
     match command {
         Subcommands::List(subargs) => {
             if !subargs.paths && !subargs.names {
-                println!("{:<17}\t{:<46}\t{:<7}", "Name", "Path", "Status");
+                println!(
+                    "{:<17}\t{:<46}\t{:<7}\t{:<5}\t{:<12}\t{:<5}\t{}",
+                    "Name", "Path", "Status", "Hints", "Difficulty", "Time", "Tags"
+                );
             }
+            let revealed_hints = RevealedHints::load();
             let mut exercises_done: u16 = 0;
             let filters = subargs.filter.clone().unwrap_or_default().to_lowercase();
+            let tags = subargs.tag.clone().unwrap_or_default().to_lowercase();
             exercises.iter().for_each(|e| {
                 let fname = format!("{}", e.path.display());
                 let filter_cond = filters
                     .split(',')
                     .filter(|f| !f.trim().is_empty())
                     .any(|f| e.name.contains(f) || fname.contains(f));
+                let tag_cond = subargs.tag.is_none()
+                    || tags
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|t| !t.is_empty())
+                        .any(|t| e.tags.iter().any(|tag| tag.to_lowercase() == t));
+                let difficulty_cond = match &subargs.difficulty {
+                    Some(difficulty) => e
+                        .difficulty
+                        .is_some_and(|d| d.to_string().eq_ignore_ascii_case(difficulty.trim())),
+                    None => true,
+                };
                 let status = if e.looks_done() {
                     exercises_done += 1;
                     "Done"
@@ -194,13 +273,27 @@ fn main() {
                         || (!e.looks_done() && subargs.unsolved)
                         || (!subargs.solved && !subargs.unsolved)
                 };
-                if solve_cond && (filter_cond || subargs.filter.is_none()) {
+                if solve_cond
+                    && (filter_cond || subargs.filter.is_none())
+                    && tag_cond
+                    && difficulty_cond
+                {
                     let line = if subargs.paths {
                         format!("{fname}\n")
                     } else if subargs.names {
                         format!("{}\n", e.name)
                     } else {
-                        format!("{:<17}\t{fname:<46}\t{status:<7}\n", e.name)
+                        let hints = format!("{}/{}", revealed_hints.level(&e.name), e.hint.len());
+                        let difficulty = e.difficulty.map(|d| d.to_string()).unwrap_or_default();
+                        let time = e
+                            .estimated_minutes
+                            .map(|minutes| format!("{minutes}m"))
+                            .unwrap_or_default();
+                        let tags = e.tags.join(",");
+                        format!(
+                            "{:<17}\t{fname:<46}\t{status:<7}\t{hints:<5}\t{difficulty:<12}\t{time:<5}\t{tags}\n",
+                            e.name
+                        )
                     };
                     // Somehow using println! leads to the binary panicking
                     // when its output is piped.
@@ -236,8 +329,15 @@ fn main() {
 
         Subcommands::Run(subargs) => {
             let exercise = find_exercise(&subargs.name, &exercises);
+            let test_filter = match subargs.test {
+                Some(pattern) => TestFilter::new(&subargs.matching, pattern).unwrap_or_else(|e| {
+                    println!("{e}");
+                    std::process::exit(1)
+                }),
+                None => TestFilter::Anything,
+            };
 
-            run(exercise).unwrap_or_else(|_| std::process::exit(1));
+            run(exercise, &test_filter).unwrap_or_else(|_| std::process::exit(1));
         }
 
         Subcommands::Reset(subargs) => {
@@ -249,13 +349,23 @@ fn main() {
         Subcommands::Hint(subargs) => {
             let exercise = find_exercise(&subargs.name, &exercises);
 
-            println!("{}", exercise.hint);
+            println!("{}", reveal_hint(&exercise.name, &exercise.hint));
         }
 
-        Subcommands::Verify(_subargs) => {
-            verify(&exercises, (0, exercises.len())).unwrap_or_else(|_| std::process::exit(1));
+        Subcommands::Verify(subargs) => {
+            cache::enable();
+            match subargs.jobs {
+                Some(jobs) if jobs > 1 => {
+                    verify_parallel(&exercises).unwrap_or_else(|_| std::process::exit(1));
+                }
+                _ => {
+                    verify(&exercises, (0, exercises.len())).unwrap_or_else(|_| std::process::exit(1));
+                }
+            }
         }
 
+        Subcommands::LintInfo(_) => unreachable!("lint-info is handled before loading the exercises"),
+
         Subcommands::Lsp(_subargs) => {
             let mut project = RustAnalyzerProject::new();
             project
@@ -276,6 +386,7 @@ fn main() {
         }
 
         Subcommands::Watch(subargs) => {
+            cache::enable();
             if subargs.solutions {
                 exercises = exercises_solution(exercises);
             }
@@ -324,8 +435,9 @@ fn exercises_solution(mut exercises: Vec<Exercise>) -> Vec<Exercise> {
 }
 
 fn spawn_watch_shell(
-    failed_exercise_hint: &Arc<Mutex<Option<String>>>,
+    failed_exercise_hint: &Arc<Mutex<Option<(String, Hints)>>>,
     should_quit: Arc<AtomicBool>,
+    should_retest: Arc<AtomicBool>,
 ) {
     let failed_exercise_hint = Arc::clone(failed_exercise_hint);
     println!("\n\nWelcome to watch mode! You can type 'help' to get an overview of the commands you can use here.");
@@ -335,18 +447,30 @@ fn spawn_watch_shell(
             Ok(_) => {
                 let input = input.trim();
                 if input == "hint" {
-                    if let Some(hint) = &*failed_exercise_hint.lock().unwrap() {
-                        println!("{hint}");
+                    if let Some((name, hints)) = &*failed_exercise_hint.lock().unwrap() {
+                        println!("{}", reveal_hint(name, hints));
                     }
                 } else if input == "clear" {
                     println!("\x1B[2J\x1B[1;1H");
+                } else if let Some(policy) = input.strip_prefix("output") {
+                    match policy.trim().parse::<OutputPolicy>() {
+                        Ok(policy) => {
+                            output::set_session_policy(policy);
+                            println!("Output of the circuits and tests: {policy}");
+                        }
+                        Err(error) => println!("{error}"),
+                    }
+                } else if input == "retest" {
+                    should_retest.store(true, Ordering::SeqCst);
                 } else if input.eq("quit") {
                     should_quit.store(true, Ordering::SeqCst);
                     println!("Bye!");
                 } else if input.eq("help") {
                     println!("Commands available to you in watch mode:");
-                    println!("  hint  - prints the current exercise's hint");
+                    println!("  hint  - reveals the next hint of the current exercise");
                     println!("  clear - clears the screen");
+                    println!("  retest - reruns only the test that failed last");
+                    println!("  output <show|capture|suppress> - sets what to do with the output of circuits and tests");
                     println!("  quit  - quits watch mode");
                     println!("  help  - displays this help message");
                     println!();
@@ -363,14 +487,11 @@ fn spawn_watch_shell(
 
 fn find_exercise<'a>(name: &str, exercises: &'a [Exercise]) -> &'a Exercise {
     if name.eq("next") {
-        exercises
-            .iter()
-            .find(|e| !e.looks_done())
-            .unwrap_or_else(|| {
-                println!("🎉 Congratulations! You have done all the exercises!");
-                println!("🔚 There are no more exercises to do next!");
-                std::process::exit(1)
-            })
+        next_exercise(exercises).unwrap_or_else(|| {
+            println!("🎉 Congratulations! You have done all the exercises!");
+            println!("🔚 There are no more exercises to do next!");
+            std::process::exit(1)
+        })
     } else {
         exercises
             .iter()
@@ -396,18 +517,28 @@ pub fn clear_screen() {
 fn watch(exercises: &[Exercise]) -> notify::Result<WatchStatus> {
     let (tx, rx) = channel();
     let should_quit = Arc::new(AtomicBool::new(false));
+    let should_retest = Arc::new(AtomicBool::new(false));
 
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_secs(2))?;
     watcher.watch(Path::new("./exercises"), RecursiveMode::Recursive)?;
 
     // clear_screen();
 
-    let to_owned_hint = |t: &Exercise| t.hint.to_owned();
-    let failed_exercise_hint = match verify(exercises.iter(), (0, exercises.len())) {
-        Ok(_) => return Ok(WatchStatus::Finished),
-        Err(exercise) => Arc::new(Mutex::new(Some(to_owned_hint(exercise)))),
-    };
-    spawn_watch_shell(&failed_exercise_hint, Arc::clone(&should_quit));
+    let to_owned_hint = |t: &Exercise| (t.name.clone(), t.hint.clone());
+    // Along with the hint, the exercise and the name of the test that failed first in the last run
+    let (failed_exercise_hint, mut last_failed_test) =
+        match verify(exercises.iter(), (0, exercises.len())) {
+            Ok(_) => return Ok(WatchStatus::Finished),
+            Err((exercise, failed_test)) => (
+                Arc::new(Mutex::new(Some(to_owned_hint(exercise)))),
+                failed_test.map(|test_name| (exercise.name.clone(), test_name)),
+            ),
+        };
+    spawn_watch_shell(
+        &failed_exercise_hint,
+        Arc::clone(&should_quit),
+        Arc::clone(&should_retest),
+    );
     loop {
         match rx.recv_timeout(Duration::from_secs(1)) {
             Ok(event) => match event {
@@ -416,17 +547,19 @@ fn watch(exercises: &[Exercise]) -> notify::Result<WatchStatus> {
                         let filepath = b.as_path().canonicalize().unwrap();
                         let pending_exercises = exercises
                             .iter()
-                            .find(|e| filepath.ends_with(&e.path))
+                            .find(|e| e.owns_file(&filepath))
                             .into_iter()
                             .chain(
                                 exercises
                                     .iter()
-                                    .filter(|e| !e.looks_done() && !filepath.ends_with(&e.path)),
+                                    .filter(|e| !e.looks_done() && !e.owns_file(&filepath)),
                             );
                         let num_done = exercises.iter().filter(|e| e.looks_done()).count();
                         match verify(pending_exercises, (num_done, exercises.len())) {
                             Ok(_) => return Ok(WatchStatus::Finished),
-                            Err(exercise) => {
+                            Err((exercise, failed_test)) => {
+                                last_failed_test =
+                                    failed_test.map(|test_name| (exercise.name.clone(), test_name));
                                 let mut failed_exercise_hint = failed_exercise_hint.lock().unwrap();
                                 *failed_exercise_hint = Some(to_owned_hint(exercise));
                             }
@@ -440,6 +573,9 @@ fn watch(exercises: &[Exercise]) -> notify::Result<WatchStatus> {
             }
             Err(e) => println!("watch error: {e:?}"),
         }
+        if should_retest.swap(false, Ordering::SeqCst) {
+            rerun_failed_test(exercises, &mut last_failed_test);
+        }
         // Check if we need to exit
         if should_quit.load(Ordering::SeqCst) {
             return Ok(WatchStatus::Unfinished);
@@ -447,6 +583,20 @@ fn watch(exercises: &[Exercise]) -> notify::Result<WatchStatus> {
     }
 }
 
+// Reruns the test that failed in the last test run, on its own
+fn rerun_failed_test(exercises: &[Exercise], last_failed_test: &mut Option<(String, String)>) {
+    let Some((exercise_name, test_name)) = last_failed_test.take() else {
+        println!("No test failed in the last run");
+        return;
+    };
+    if let Some(exercise) = exercises.iter().find(|e| e.name == exercise_name) {
+        // The failure is already reported by the run
+        if let Err(error) = run(exercise, &TestFilter::Exact(test_name)) {
+            *last_failed_test = failed_test(&error).map(|test_name| (exercise_name, test_name));
+        }
+    }
+}
+
 fn rustc_exists() -> bool {
     Command::new("rustc")
         .args(["--version"])
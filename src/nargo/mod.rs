@@ -1,8 +1,3 @@
-pub mod noir_compile;
-pub use noir_compile::*;
-pub mod noir_run;
-pub use noir_run::*;
-
 pub mod noir_test;
 pub use noir_test::*;
 
@@ -15,5 +10,8 @@ pub use noir_fs::*;
 pub mod noir_execute;
 pub use noir_execute::*;
 
+pub mod noir_output;
+pub use noir_output::*;
+
 pub mod noir_errors;
 pub use noir_errors::*;
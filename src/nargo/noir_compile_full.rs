@@ -1,45 +1,70 @@
-use std::path::Path;
-
 use acvm::acir::circuit::ExpressionWidth;
 use fm::FileManager;
 use nargo::{
-    insert_all_files_for_workspace_into_file_manager,
     ops::{collect_errors, compile_contract, compile_program, report_errors},
     package::Package,
-    parse_all,
     workspace::Workspace,
 };
-use noirc_driver::{
-    CompilationResult, CompileOptions, CompiledContract, NOIR_ARTIFACT_VERSION_STRING,
-};
+use noirc_driver::{CompilationResult, CompileOptions, CompiledContract, CompiledProgram};
 use noirc_frontend::hir::ParsedFiles;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 
-use super::{read_program_from_file, save_contract_to_file, save_program_to_file};
+/// The programs and contracts compiled from a workspace, kept in memory.
+/// Programs are paired with the package they were compiled from.
+pub struct CompiledWorkspace {
+    pub programs: Vec<(Package, CompiledProgram)>,
+    pub contracts: Vec<CompiledContract>,
+}
 
 pub fn cli_compile_workspace_full(
+    file_manager: &FileManager,
+    parsed_files: &ParsedFiles,
     workspace: &Workspace,
     compile_options: &CompileOptions,
-) -> Result<(), anyhow::Error> {
-    let mut workspace_file_manager = workspace.new_file_manager();
-    insert_all_files_for_workspace_into_file_manager(workspace, &mut workspace_file_manager);
-    let parsed_files = parse_all(&workspace_file_manager);
-
-    let compiled_workspace = cli_compile_workspace(
-        &workspace_file_manager,
-        &parsed_files,
-        workspace,
-        compile_options,
-    );
+) -> Result<CompiledWorkspace, anyhow::Error> {
+    let compiled_workspace =
+        cli_compile_workspace(file_manager, parsed_files, workspace, compile_options);
 
-    report_errors(
+    let compiled_workspace = report_errors(
         compiled_workspace,
-        &workspace_file_manager,
+        file_manager,
         compile_options.deny_warnings,
         compile_options.silence_warnings,
     )?;
 
-    Ok(())
+    Ok(compiled_workspace)
+}
+
+/// Compiles the workspace when it is expected to fail.
+/// Every diagnostic is reported, and the messages of the errors are returned
+/// so they can be matched. Returns `None` if the compilation succeeded.
+pub fn cli_compile_workspace_errors(
+    file_manager: &FileManager,
+    parsed_files: &ParsedFiles,
+    workspace: &Workspace,
+    compile_options: &CompileOptions,
+) -> Option<Vec<String>> {
+    let diagnostics =
+        cli_compile_workspace(file_manager, parsed_files, workspace, compile_options).err()?;
+
+    noirc_errors::reporter::report_all(
+        file_manager.as_file_map(),
+        &diagnostics,
+        compile_options.deny_warnings,
+        compile_options.silence_warnings,
+    );
+
+    let messages = diagnostics
+        .iter()
+        .filter(|file_diagnostic| file_diagnostic.diagnostic.is_error())
+        .flat_map(|file_diagnostic| {
+            let diagnostic = &file_diagnostic.diagnostic;
+            std::iter::once(diagnostic.message.clone())
+                .chain(diagnostic.secondaries.iter().map(|label| label.message.clone()))
+                .chain(diagnostic.notes.iter().cloned())
+        })
+        .collect();
+    Some(messages)
 }
 
 fn cli_compile_workspace(
@@ -47,7 +72,7 @@ fn cli_compile_workspace(
     parsed_files: &ParsedFiles,
     workspace: &Workspace,
     compile_options: &CompileOptions,
-) -> CompilationResult<()> {
+) -> CompilationResult<CompiledWorkspace> {
     let (binary_packages, contract_packages): (Vec<_>, Vec<_>) = workspace
         .into_iter()
         .filter(|package| !package.is_library())
@@ -55,25 +80,24 @@ fn cli_compile_workspace(
         .partition(|package| package.is_binary());
 
     // Compile all of the packages in parallel.
-    let program_warnings_or_errors: CompilationResult<()> = cli_compile_programs(
+    let program_warnings_or_errors = cli_compile_programs(
         file_manager,
         parsed_files,
         workspace,
         &binary_packages,
         compile_options,
     );
-    let contract_warnings_or_errors: CompilationResult<()> = cli_compiled_contracts(
+    let contract_warnings_or_errors = cli_compiled_contracts(
         file_manager,
         parsed_files,
         &contract_packages,
         compile_options,
-        &workspace.target_directory_path(),
     );
 
     match (program_warnings_or_errors, contract_warnings_or_errors) {
-        (Ok((_, program_warnings)), Ok((_, contract_warnings))) => {
+        (Ok((programs, program_warnings)), Ok((contracts, contract_warnings))) => {
             let warnings = [program_warnings, contract_warnings].concat();
-            Ok(((), warnings))
+            Ok((CompiledWorkspace { programs, contracts }, warnings))
         }
         (Err(program_errors), Err(contract_errors)) => {
             Err([program_errors, contract_errors].concat())
@@ -88,36 +112,22 @@ fn cli_compile_programs(
     workspace: &Workspace,
     binary_packages: &[Package],
     compile_options: &CompileOptions,
-) -> CompilationResult<()> {
-    let load_cached_program = |package| {
-        let program_artifact_path = workspace.package_build_path(package);
-        read_program_from_file(program_artifact_path)
-            .ok()
-            .filter(|p| p.noir_version == NOIR_ARTIFACT_VERSION_STRING)
-            .map(|p| p.into())
-    };
-
-    let compile_package = |package| {
+) -> CompilationResult<Vec<(Package, CompiledProgram)>> {
+    let compile_package = |package: &Package| {
         let (program, warnings) = compile_program(
             file_manager,
             parsed_files,
             workspace,
             package,
             compile_options,
-            load_cached_program(package),
+            None,
         )?;
 
         let target_width =
             cli_get_target_width(package.expression_width, compile_options.expression_width);
         let program = nargo::ops::transform_program(program, target_width);
 
-        save_program_to_file(
-            &program.into(),
-            &package.name,
-            workspace.target_directory_path(),
-        );
-
-        Ok(((), warnings))
+        Ok(((package.clone(), program), warnings))
     };
 
     // Configure a thread pool with a larger stack size to prevent overflowing stack in large programs.
@@ -126,11 +136,11 @@ fn cli_compile_programs(
         .stack_size(4 * 1024 * 1024)
         .build()
         .unwrap();
-    let program_results: Vec<CompilationResult<()>> =
+    let program_results: Vec<CompilationResult<(Package, CompiledProgram)>> =
         pool.install(|| binary_packages.par_iter().map(compile_package).collect());
 
     // Collate any warnings/errors which were encountered during compilation.
-    collect_errors(program_results).map(|(_, warnings)| ((), warnings))
+    collect_errors(program_results)
 }
 
 fn cli_compiled_contracts(
@@ -138,9 +148,8 @@ fn cli_compiled_contracts(
     parsed_files: &ParsedFiles,
     contract_packages: &[Package],
     compile_options: &CompileOptions,
-    target_dir: &Path,
-) -> CompilationResult<()> {
-    let contract_results: Vec<CompilationResult<()>> = contract_packages
+) -> CompilationResult<Vec<CompiledContract>> {
+    let contract_results: Vec<CompilationResult<CompiledContract>> = contract_packages
         .par_iter()
         .map(|package| {
             let (contract, warnings) =
@@ -148,35 +157,12 @@ fn cli_compiled_contracts(
             let target_width =
                 cli_get_target_width(package.expression_width, compile_options.expression_width);
             let contract = nargo::ops::transform_contract(contract, target_width);
-            cli_save_contract(
-                contract,
-                package,
-                target_dir,
-                compile_options.show_artifact_paths,
-            );
-            Ok(((), warnings))
+            Ok((contract, warnings))
         })
         .collect();
 
     // Collate any warnings/errors which were encountered during compilation.
-    collect_errors(contract_results).map(|(_, warnings)| ((), warnings))
-}
-
-fn cli_save_contract(
-    contract: CompiledContract,
-    package: &Package,
-    target_dir: &Path,
-    show_artifact_paths: bool,
-) {
-    let contract_name = contract.name.clone();
-    let artifact_path = save_contract_to_file(
-        &contract.into(),
-        &format!("{}-{}", package.name, contract_name),
-        target_dir,
-    );
-    if show_artifact_paths {
-        println!("Saved contract artifact to: {}", artifact_path.display());
-    }
+    collect_errors(contract_results)
 }
 
 /// Default expression width used for Noir compilation.
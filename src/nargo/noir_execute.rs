@@ -1,40 +1,68 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use acvm::{acir::native_types::WitnessStack, FieldElement};
 use bn254_blackbox_solver::Bn254BlackBoxSolver;
-use nargo::{errors::try_to_diagnose_runtime_error, ops::DefaultForeignCallExecutor, package::Package};
-use noirc_abi::{input_parser::{Format, InputValue}, InputMap};
+use nargo::errors::try_to_diagnose_runtime_error;
+use noirc_abi::{
+    input_parser::{Format, InputValue},
+    Abi, InputMap, MAIN_RETURN_NAME,
+};
 use noirc_artifacts::debug::DebugArtifact;
 use noirc_driver::CompiledProgram;
 use anyhow::Error;
 
-use super::read_inputs_from_file;
 
+use super::noir_output::{OutputForeignCallExecutor, ProgramOutput};
 
+/// Executes the program with the inputs of the given Prover.toml content and decodes its return value.
+/// Returns the decoded return value, the `return` entry of the prover file (if any)
+/// and the solved witness stack.
 pub fn execute_program_and_decode(
-    program: CompiledProgram,
-    package: &Package,
-    prover_name: &str,
+    program: &CompiledProgram,
+    prover_toml: &str,
+    output: &mut ProgramOutput,
     foreign_call_resolver_url: Option<&str>,
     root_path: Option<PathBuf>,
     package_name: Option<String>,
-) -> Result<(Option<InputValue>, WitnessStack<FieldElement>), Error> {
-    // Parse the initial witness values from Prover.toml
-    let (inputs_map, _) =
-        read_inputs_from_file(&package.root_dir, prover_name, Format::Toml, &program.abi)?;
-    let witness_stack =
-        execute_program(&program, &inputs_map, foreign_call_resolver_url, root_path, package_name)?;
+) -> Result<(Option<InputValue>, Option<InputValue>, WitnessStack<FieldElement>), Error> {
+    // Parse the initial witness values from the Prover.toml
+    let (inputs_map, expected_return) = parse_prover_inputs(prover_toml, &program.abi)?;
+    let witness_stack = execute_program(
+        program,
+        &inputs_map,
+        output,
+        foreign_call_resolver_url,
+        root_path,
+        package_name,
+    )?;
     // Get the entry point witness for the ABI
     let main_witness =
         &witness_stack.peek().expect("Should have at least one witness on the stack").witness;
     let (_, return_value) = program.abi.decode(main_witness)?;
 
-    Ok((return_value, witness_stack))
+    Ok((return_value, expected_return, witness_stack))
+}
+
+/// Parses the inputs of the circuit from the content of a Prover.toml,
+/// splitting off its `return` entry.
+pub(crate) fn parse_prover_inputs(
+    prover_toml: &str,
+    abi: &Abi,
+) -> Result<(InputMap, Option<InputValue>), Error> {
+    if abi.is_empty() {
+        return Ok((BTreeMap::new(), None));
+    }
+
+    let mut inputs_map = Format::Toml.parse(prover_toml, abi)?;
+    let return_value = inputs_map.remove(MAIN_RETURN_NAME);
+
+    Ok((inputs_map, return_value))
 }
 
 pub(crate) fn execute_program(
     compiled_program: &CompiledProgram,
     inputs_map: &InputMap,
+    output: &mut ProgramOutput,
     foreign_call_resolver_url: Option<&str>,
     root_path: Option<PathBuf>,
     package_name: Option<String>,
@@ -45,8 +73,8 @@ pub(crate) fn execute_program(
         &compiled_program.program,
         initial_witness,
         &Bn254BlackBoxSolver,
-        &mut DefaultForeignCallExecutor::new(
-            true,
+        &mut OutputForeignCallExecutor::new(
+            output,
             foreign_call_resolver_url,
             root_path,
             package_name,
@@ -1,52 +1,15 @@
 use acvm::{acir::native_types::WitnessStack, FieldElement};
 use nargo::constants::WITNESS_EXT;
-use noirc_abi::{
-    input_parser::{Format, InputValue},
-    Abi, InputMap, MAIN_RETURN_NAME,
-};
-use noirc_artifacts::{contract::ContractArtifact, program::ProgramArtifact};
+use noirc_artifacts::program::ProgramArtifact;
 use noirc_frontend::graph::CrateName;
-use std::{collections::BTreeMap, fs::File, io::Write, path::{Path, PathBuf}};
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use super::FilesystemError;
 
-
-/// Returns the circuit's parameters and its return value, if one exists.
-/// # Examples
-///
-/// ```ignore
-/// let (input_map, return_value): (InputMap, Option<InputValue>) =
-///   read_inputs_from_file(path, "Verifier", Format::Toml, &abi)?;
-/// ```
-pub(crate) fn read_inputs_from_file<P: AsRef<Path>>(
-    path: P,
-    file_name: &str,
-    format: Format,
-    abi: &Abi,
-) -> Result<(InputMap, Option<InputValue>), FilesystemError> {
-    if abi.is_empty() {
-        return Ok((BTreeMap::new(), None));
-    }
-
-    let file_path = path.as_ref().join(file_name).with_extension(format.ext());
-    if !file_path.exists() {
-        if abi.parameters.is_empty() {
-            // Reading a return value from the `Prover.toml` is optional,
-            // so if the ABI has no parameters we can skip reading the file if it doesn't exist.
-            return Ok((BTreeMap::new(), None));
-        } else {
-            return Err(FilesystemError::MissingTomlFile(file_name.to_owned(), file_path));
-        }
-    }
-
-    let input_string = std::fs::read_to_string(file_path).unwrap();
-    let mut input_map = format.parse(&input_string, abi)?;
-    let return_value = input_map.remove(MAIN_RETURN_NAME);
-
-    Ok((input_map, return_value))
-}
-
-
 pub(crate) fn read_program_from_file<P: AsRef<Path>>(
     circuit_path: P,
 ) -> Result<ProgramArtifact, FilesystemError> {
@@ -60,7 +23,6 @@ pub(crate) fn read_program_from_file<P: AsRef<Path>>(
     Ok(program)
 }
 
-
 pub(crate) fn save_program_to_file<P: AsRef<Path>>(
     program_artifact: &ProgramArtifact,
     crate_name: &CrateName,
@@ -70,15 +32,6 @@ pub(crate) fn save_program_to_file<P: AsRef<Path>>(
     save_build_artifact_to_file(program_artifact, &circuit_name, circuit_dir)
 }
 
-pub(crate) fn save_contract_to_file<P: AsRef<Path>>(
-    compiled_contract: &ContractArtifact,
-    circuit_name: &str,
-    circuit_dir: P,
-) -> PathBuf {
-    save_build_artifact_to_file(compiled_contract, circuit_name, circuit_dir)
-}
-
-
 fn save_build_artifact_to_file<P: AsRef<Path>, T: ?Sized + serde::Serialize>(
     build_artifact: &T,
     artifact_name: &str,
@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+
+use acvm::{
+    acir::brillig::{ForeignCallParam, ForeignCallResult},
+    pwg::ForeignCallWaitInfo,
+    AcirField, FieldElement,
+};
+use nargo::ops::{DefaultForeignCallExecutor, ForeignCall, ForeignCallExecutor};
+use noirc_printable_type::{ForeignCallError, PrintableValueDisplay};
+
+use crate::output::{report, OutputPolicy};
+
+/// The output printed by the circuits and the tests of a run, handled according to its policy
+pub struct ProgramOutput {
+    policy: OutputPolicy,
+    captured: String,
+}
+
+impl ProgramOutput {
+    pub fn new(policy: OutputPolicy) -> ProgramOutput {
+        ProgramOutput {
+            policy,
+            captured: String::new(),
+        }
+    }
+
+    pub fn policy(&self) -> OutputPolicy {
+        self.policy
+    }
+
+    /// The output captured so far, empty unless the policy is to capture it
+    pub fn captured(&self) -> &str {
+        &self.captured
+    }
+
+    pub fn into_captured(self) -> String {
+        self.captured
+    }
+
+    /// Prints the text with the report of the run, captures it or drops it, according to the policy
+    pub fn print(&mut self, text: &str) {
+        match self.policy {
+            OutputPolicy::Show => report(text),
+            OutputPolicy::Capture => self.captured.push_str(text),
+            OutputPolicy::Suppress => {}
+        }
+    }
+}
+
+/// Resolves the foreign calls like nargo does, but sends the `print` calls to a `ProgramOutput`
+pub struct OutputForeignCallExecutor<'a> {
+    output: &'a mut ProgramOutput,
+    executor: DefaultForeignCallExecutor<FieldElement>,
+}
+
+impl<'a> OutputForeignCallExecutor<'a> {
+    pub fn new(
+        output: &'a mut ProgramOutput,
+        foreign_call_resolver_url: Option<&str>,
+        root_path: Option<PathBuf>,
+        package_name: Option<String>,
+    ) -> OutputForeignCallExecutor<'a> {
+        OutputForeignCallExecutor {
+            output,
+            // Never prints itself, the prints are handled here
+            executor: DefaultForeignCallExecutor::new(
+                false,
+                foreign_call_resolver_url,
+                root_path,
+                package_name,
+            ),
+        }
+    }
+}
+
+impl ForeignCallExecutor<FieldElement> for OutputForeignCallExecutor<'_> {
+    fn execute(
+        &mut self,
+        foreign_call: &ForeignCallWaitInfo<FieldElement>,
+    ) -> Result<ForeignCallResult<FieldElement>, ForeignCallError> {
+        if !matches!(
+            ForeignCall::lookup(&foreign_call.function),
+            Some(ForeignCall::Print)
+        ) {
+            return self.executor.execute(foreign_call);
+        }
+
+        // Like nargo: a flag telling whether to skip the newline, then the printed value
+        let (newline_flag, printed_value) = foreign_call
+            .inputs
+            .split_first()
+            .ok_or(ForeignCallError::MissingForeignCallInputs)?;
+        let display_value: PrintableValueDisplay<FieldElement> = printed_value.try_into()?;
+        let skip_newline = match newline_flag {
+            ForeignCallParam::Single(flag) => flag.is_zero(),
+            ForeignCallParam::Array(_) => false,
+        };
+        let newline = if skip_newline { "" } else { "\n" };
+        self.output.print(&format!("{display_value}{newline}"));
+
+        Ok(ForeignCallResult { values: Vec::new() })
+    }
+}
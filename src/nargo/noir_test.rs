@@ -1,56 +1,143 @@
-use acvm::{BlackBoxFunctionSolver, FieldElement};
+use acvm::{
+    acir::native_types::{WitnessMap, WitnessStack},
+    BlackBoxFunctionSolver, FieldElement,
+};
 use fm::FileManager;
-use nargo::{errors::CompileError, ops::{report_errors, TestStatus}, package::Package, prepare_package};
-use noirc_frontend::{graph::CrateId, hir::{Context, FunctionNameMatch, ParsedFiles}};
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
-use std::{io::Write, path::PathBuf};
+use nargo::{
+    errors::{try_to_diagnose_runtime_error, CompileError},
+    ops::{report_errors, TestStatus},
+    package::Package,
+    prepare_package, NargoError,
+};
+use noirc_frontend::{
+    graph::CrateId,
+    hir::{def_map::TestFunction, Context, FunctionNameMatch, ParsedFiles},
+};
+use rayon::prelude::*;
+use std::{
+    fmt::{self, Display, Formatter},
+    io::Write,
+    path::PathBuf,
+};
+use termcolor::{Buffer, Color, ColorSpec, WriteColor};
 
-use noirc_driver::{check_crate, CompileOptions};
+use noirc_driver::{check_crate, compile_no_check, CompileOptions, CompiledProgram};
+use noirc_errors::FileDiagnostic;
 
+use super::noir_output::{OutputForeignCallExecutor, ProgramOutput};
+use crate::output::{is_capturing_report, OutputPolicy};
+
+/// Selects the test functions to run by name. A name is matched as a whole
+/// (e.g. `grader::test_sum`) or by its last segment (`test_sum`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TestFilter {
+    Anything,
+    Exact(String),
+    Contains(String),
+    StartsWith(String),
+}
+
+impl TestFilter {
+    /// Builds the filter matching `pattern` the given way: exact, contains or starts-with
+    pub fn new(matching: &str, pattern: String) -> Result<TestFilter, String> {
+        match matching {
+            "exact" => Ok(TestFilter::Exact(pattern)),
+            "contains" => Ok(TestFilter::Contains(pattern)),
+            "starts-with" => Ok(TestFilter::StartsWith(pattern)),
+            _ => Err(format!(
+                "unknown test matching `{matching}`, expected exact, contains or starts-with"
+            )),
+        }
+    }
+
+    pub fn matches(&self, test_name: &str) -> bool {
+        let short_name = test_name.rsplit("::").next().unwrap_or(test_name);
+        match self {
+            TestFilter::Anything => true,
+            TestFilter::Exact(pattern) => test_name == pattern || short_name == pattern,
+            TestFilter::Contains(pattern) => test_name.contains(pattern.as_str()),
+            TestFilter::StartsWith(pattern) => {
+                test_name.starts_with(pattern.as_str()) || short_name.starts_with(pattern.as_str())
+            }
+        }
+    }
+}
+
+impl Display for TestFilter {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            TestFilter::Anything => write!(f, "any name"),
+            TestFilter::Exact(pattern) => write!(f, "`{pattern}`"),
+            TestFilter::Contains(pattern) => write!(f, "a name containing `{pattern}`"),
+            TestFilter::StartsWith(pattern) => write!(f, "a name starting with `{pattern}`"),
+        }
+    }
+}
 
 #[allow(clippy::too_many_arguments)]
 pub fn run_tests<S: BlackBoxFunctionSolver<FieldElement> + Default>(
     file_manager: &FileManager,
     parsed_files: &ParsedFiles,
     package: &Package,
-    fn_name: FunctionNameMatch,
-    show_output: bool,
+    filter: &TestFilter,
+    output: &mut ProgramOutput,
     foreign_call_resolver_url: Option<&str>,
     root_path: Option<PathBuf>,
     package_name: Option<String>,
     compile_options: &CompileOptions,
 ) -> Result<Vec<(String, TestStatus)>, CompileError> {
-    let test_functions = get_tests_in_package(
-        file_manager,
-        parsed_files,
-        package,
-        fn_name,
-        compile_options,
-    )?;
+    let test_functions =
+        get_tests_in_package(file_manager, parsed_files, package, filter, compile_options)?;
 
     let count_all = test_functions.len();
 
     let plural = if count_all == 1 { "" } else { "s" };
-    println!(
+    report!(
         "[{}] Running {count_all} test function{plural}",
         package.name
     );
 
-    let test_report: Vec<(String, TestStatus)> = test_functions
-        .into_iter()
-        .map(|test_name| {
-            let status = run_test::<S>(
-                file_manager,
-                parsed_files,
-                package,
-                &test_name,
-                show_output,
-                foreign_call_resolver_url,
-                root_path.clone(),
-                package_name.clone(),
-                compile_options,
-            );
+    // `Context` can't be shared across threads: the tests are split in one chunk per thread,
+    // each one checking the crate once, then compiling and running its tests from it.
+    // The output is buffered per test, so the tests running at once don't mix their prints.
+    let policy = output.policy();
+    let test_policy = match policy {
+        OutputPolicy::Show | OutputPolicy::Capture => OutputPolicy::Capture,
+        OutputPolicy::Suppress => OutputPolicy::Suppress,
+    };
+    let chunk_size = count_all.div_ceil(rayon::current_num_threads()).max(1);
+    let chunk_results: Vec<Vec<(String, TestStatus, String)>> = test_functions
+        .par_chunks(chunk_size)
+        .map(|test_names| {
+            let (mut context, crate_id) = prepare_package(file_manager, parsed_files, package);
+            check_crate(&mut context, crate_id, compile_options)
+                .expect("Any errors should have occurred when collecting test functions");
+            test_names
+                .iter()
+                .map(|test_name| {
+                    let mut test_output = ProgramOutput::new(test_policy);
+                    let status = run_test::<S>(
+                        &mut context,
+                        crate_id,
+                        test_name,
+                        &mut test_output,
+                        foreign_call_resolver_url,
+                        root_path.clone(),
+                        package_name.clone(),
+                        compile_options,
+                    );
+                    (test_name.clone(), status, test_output.into_captured())
+                })
+                .collect()
+        })
+        .collect();
 
+    // The buffered outputs are given in the order of the tests
+    let test_report: Vec<(String, TestStatus)> = chunk_results
+        .into_iter()
+        .flatten()
+        .map(|(test_name, status, captured)| {
+            output.print(&captured);
             (test_name, status)
         })
         .collect();
@@ -59,56 +146,130 @@ pub fn run_tests<S: BlackBoxFunctionSolver<FieldElement> + Default>(
     Ok(test_report)
 }
 
+// Compiles and runs a test function of the crate checked in `context`.
+// What it prints goes to `output`, where nargo would print it or drop it.
+#[allow(clippy::too_many_arguments)]
 fn run_test<S: BlackBoxFunctionSolver<FieldElement> + Default>(
-    file_manager: &FileManager,
-    parsed_files: &ParsedFiles,
-    package: &Package,
+    context: &mut Context,
+    crate_id: CrateId,
     fn_name: &str,
-    show_output: bool,
+    output: &mut ProgramOutput,
     foreign_call_resolver_url: Option<&str>,
     root_path: Option<PathBuf>,
     package_name: Option<String>,
     compile_options: &CompileOptions,
 ) -> TestStatus {
-    // This is really hacky but we can't share `Context` or `S` across threads.
-    // We then need to construct a separate copy for each test.
-
-    let (mut context, crate_id) = prepare_package(file_manager, parsed_files, package);
-    check_crate(&mut context, crate_id, compile_options)
-        .expect("Any errors should have occurred when collecting test functions");
-
     let test_functions = context
         .get_all_test_functions_in_crate_matching(&crate_id, FunctionNameMatch::Exact(fn_name));
     let (_, test_function) = test_functions.first().expect("Test function should exist");
 
     let blackbox_solver = S::default();
 
-    nargo::ops::run_test(
+    // Tests taking arguments are fuzzed, and tests failing to compile never run:
+    // both are left to nargo, they print nothing to capture
+    let has_arguments = !context
+        .def_interner
+        .function_meta(&test_function.get_id())
+        .function_signature()
+        .0
+        .is_empty();
+    let compiled_program = if has_arguments {
+        None
+    } else {
+        compile_no_check(
+            context,
+            compile_options,
+            test_function.get_id(),
+            None,
+            false,
+        )
+        .ok()
+    };
+    let Some(compiled_program) = compiled_program else {
+        return nargo::ops::run_test(
+            &blackbox_solver,
+            context,
+            test_function,
+            false,
+            foreign_call_resolver_url,
+            root_path,
+            package_name,
+            compile_options,
+        );
+    };
+
+    let circuit_execution = nargo::ops::execute_program(
+        &compiled_program.program,
+        WitnessMap::new(),
         &blackbox_solver,
-        &mut context,
-        test_function,
-        show_output,
-        foreign_call_resolver_url,
-        root_path,
-        package_name,
-        compile_options,
-    )
+        &mut OutputForeignCallExecutor::new(
+            output,
+            foreign_call_resolver_url,
+            root_path,
+            package_name,
+        ),
+    );
+    test_status(test_function, &compiled_program, circuit_execution)
+}
+
+// The status of an executed test, decided the way nargo does
+fn test_status(
+    test_function: &TestFunction,
+    compiled_program: &CompiledProgram,
+    circuit_execution: Result<WitnessStack<FieldElement>, NargoError<FieldElement>>,
+) -> TestStatus {
+    let error = match circuit_execution {
+        Ok(_) if test_function.should_fail() => {
+            return TestStatus::Fail {
+                message: "error: Test passed when it should have failed".to_string(),
+                error_diagnostic: None,
+            };
+        }
+        Ok(_) => return TestStatus::Pass,
+        Err(error) => error,
+    };
+
+    let error_diagnostic =
+        try_to_diagnose_runtime_error(&error, &compiled_program.abi, &compiled_program.debug);
+    if !test_function.should_fail() {
+        return TestStatus::Fail {
+            message: error.to_string(),
+            error_diagnostic,
+        };
+    }
+
+    // `should_fail_with` also expects the failure message
+    let Some(expected_message) = test_function.failure_reason() else {
+        return TestStatus::Pass;
+    };
+    let failure_message = error.user_defined_failure_message(&compiled_program.abi.error_types);
+    if failure_message.as_deref() == Some(expected_message) {
+        return TestStatus::Pass;
+    }
+    TestStatus::Fail {
+        message: format!(
+            "\nerror: Test failed with the wrong message. \nExpected: {expected_message} \nGot: {}",
+            failure_message.unwrap_or_else(|| "assertion failed".to_string())
+        ),
+        error_diagnostic,
+    }
 }
 
 fn get_tests_in_package(
     file_manager: &FileManager,
     parsed_files: &ParsedFiles,
     package: &Package,
-    fn_name: FunctionNameMatch,
+    filter: &TestFilter,
     options: &CompileOptions,
 ) -> Result<Vec<String>, CompileError> {
     let (mut context, crate_id) = prepare_package(file_manager, parsed_files, package);
     check_crate_and_report_errors(&mut context, crate_id, options)?;
 
     Ok(context
-        .get_all_test_functions_in_crate_matching(&crate_id, fn_name)
+        .get_all_test_functions_in_crate_matching(&crate_id, FunctionNameMatch::Anything)
         .into_iter()
         .map(|(test_name, _)| test_name)
+        .filter(|test_name| filter.matches(test_name))
         .collect())
 }
 
@@ -118,20 +279,19 @@ fn display_test_report(
     compile_options: &CompileOptions,
     test_report: &[(String, TestStatus)],
 ) -> Result<(), CompileError> {
-    let writer = StandardStream::stderr(ColorChoice::Always);
-    let mut writer = writer.lock();
+    // Written as a whole to the report of the run, which may be captured
+    let mut writer = Buffer::ansi();
 
     for (test_name, test_status) in test_report {
         write!(writer, "[{}] Testing {test_name}... ", package.name)
-            .expect("Failed to write to stderr");
-        writer.flush().expect("Failed to flush writer");
+            .expect("Failed to write the test report");
 
         match &test_status {
             TestStatus::Pass { .. } => {
                 writer
                     .set_color(ColorSpec::new().set_fg(Some(Color::Green)))
                     .expect("Failed to set color");
-                writeln!(writer, "ok").expect("Failed to write to stderr");
+                writeln!(writer, "ok").expect("Failed to write the test report");
             }
             TestStatus::Fail {
                 message,
@@ -140,29 +300,19 @@ fn display_test_report(
                 writer
                     .set_color(ColorSpec::new().set_fg(Some(Color::Red)))
                     .expect("Failed to set color");
-                writeln!(writer, "FAIL\n{message}\n").expect("Failed to write to stderr");
+                writeln!(writer, "FAIL\n{message}\n").expect("Failed to write the test report");
                 if let Some(diag) = error_diagnostic {
-                    noirc_errors::reporter::report_all(
-                        file_manager.as_file_map(),
-                        &[diag.clone()],
-                        compile_options.deny_warnings,
-                        compile_options.silence_warnings,
-                    );
+                    report_diagnostic(&mut writer, file_manager, compile_options, diag);
                 }
             }
             TestStatus::CompileError(err) => {
-                noirc_errors::reporter::report_all(
-                    file_manager.as_file_map(),
-                    &[err.clone()],
-                    compile_options.deny_warnings,
-                    compile_options.silence_warnings,
-                );
+                report_diagnostic(&mut writer, file_manager, compile_options, err);
             }
         }
         writer.reset().expect("Failed to reset writer");
     }
 
-    write!(writer, "[{}] ", package.name).expect("Failed to write to stderr");
+    write!(writer, "[{}] ", package.name).expect("Failed to write the test report");
 
     let count_all = test_report.len();
     let count_failed = test_report
@@ -174,9 +324,9 @@ fn display_test_report(
         writer
             .set_color(ColorSpec::new().set_fg(Some(Color::Green)))
             .expect("Failed to set color");
-        write!(writer, "{count_all} test{plural} passed").expect("Failed to write to stderr");
+        write!(writer, "{count_all} test{plural} passed").expect("Failed to write the test report");
         writer.reset().expect("Failed to reset writer");
-        writeln!(writer).expect("Failed to write to stderr");
+        writeln!(writer).expect("Failed to write the test report");
     } else {
         let count_passed = count_all - count_failed;
         let plural_failed = if count_failed == 1 { "" } else { "s" };
@@ -187,20 +337,43 @@ fn display_test_report(
                 .set_color(ColorSpec::new().set_fg(Some(Color::Green)))
                 .expect("Failed to set color");
             write!(writer, "{count_passed} test{plural_passed} passed, ",)
-                .expect("Failed to write to stderr");
+                .expect("Failed to write the test report");
         }
 
         writer
             .set_color(ColorSpec::new().set_fg(Some(Color::Red)))
             .expect("Failed to set color");
         writeln!(writer, "{count_failed} test{plural_failed} failed")
-            .expect("Failed to write to stderr");
+            .expect("Failed to write the test report");
         writer.reset().expect("Failed to reset writer");
     }
 
+    crate::output::report(&String::from_utf8_lossy(writer.as_slice()));
     Ok(())
 }
 
+// Reports the diagnostic of a failed test. nargo renders it, with its source, on stderr:
+// when the report is captured it's written out plainly with the rest of the report instead.
+fn report_diagnostic(
+    writer: &mut Buffer,
+    file_manager: &FileManager,
+    compile_options: &CompileOptions,
+    diagnostic: &FileDiagnostic,
+) {
+    if is_capturing_report() {
+        writeln!(writer, "{}", diagnostic.diagnostic).expect("Failed to write the test report");
+        return;
+    }
+    crate::output::report(&String::from_utf8_lossy(writer.as_slice()));
+    writer.clear();
+    noirc_errors::reporter::report_all(
+        file_manager.as_file_map(),
+        &[diagnostic.clone()],
+        compile_options.deny_warnings,
+        compile_options.silence_warnings,
+    );
+}
+
 /// Run the lexing, parsing, name resolution, and type checking passes and report any warnings
 /// and errors found.
 pub(crate) fn check_crate_and_report_errors(
@@ -216,3 +389,22 @@ pub(crate) fn check_crate_and_report_errors(
         options.silence_warnings,
     )
 }
+
+#[test]
+fn test_filter_matches_names() {
+    let exact = TestFilter::new("exact", "test_insert".to_string()).unwrap();
+    assert!(exact.matches("test_insert"));
+    assert!(exact.matches("grader::test_insert"));
+    assert!(!exact.matches("test_insert_twice"));
+
+    let contains = TestFilter::new("contains", "insert".to_string()).unwrap();
+    assert!(contains.matches("grader::test_insert_twice"));
+    assert!(!contains.matches("test_remove"));
+
+    let starts_with = TestFilter::new("starts-with", "test_ins".to_string()).unwrap();
+    assert!(starts_with.matches("grader::test_insert"));
+    assert!(!starts_with.matches("test_remove"));
+
+    assert!(TestFilter::Anything.matches("grader::test_hidden"));
+    assert!(TestFilter::new("regex", "test".to_string()).is_err());
+}
@@ -1,250 +1,1178 @@
+use acvm::{
+    acir::circuit::{ErrorSelector, ExpressionWidth},
+    FieldElement,
+};
 use bn254_blackbox_solver::Bn254BlackBoxSolver;
+use fm::FileManager;
+use glob::glob;
 use nargo::{
-    constants::PROVER_INPUT_FILE, insert_all_files_for_workspace_into_file_manager, ops::TestStatus, parse_all
+    insert_all_files_for_workspace_into_file_manager,
+    ops::TestStatus,
+    package::{Dependency, Package, PackageType},
+    parse_all,
+    workspace::Workspace,
+    NargoError,
 };
 use nargo_toml::{get_package_manifest, resolve_workspace_from_toml, PackageSelection};
-use noirc_frontend::hir::FunctionNameMatch;
+use console::style;
+use noirc_abi::{
+    input_parser::{Format, InputValue},
+    Abi, AbiErrorType, MAIN_RETURN_NAME,
+};
+use noirc_artifacts::{contract::ContractArtifact, program::ProgramArtifact};
+use noirc_frontend::{graph::CrateName, hir::ParsedFiles};
 use std::{
     env::current_dir,
-    fs::{self},
-    path::PathBuf, process::Command,
+    fs::{self, OpenOptions},
+    collections::{BTreeMap, BTreeSet},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+use noirc_driver::{
+    file_manager_with_stdlib, CompileOptions, CompiledProgram, NOIR_ARTIFACT_VERSION_STRING,
+};
+
+use crate::{
+    backend::{proving_backend, BackendArtifacts},
+    exercise::{
+        BbVerifyOptions, ContractOptions, DerivedArtifact, DerivedInput, ExecuteCase, Exercise,
+        Mode, TomlFile,
+    },
+    nargo::{
+        cli_compile_workspace_errors, cli_compile_workspace_full, execute_program_and_decode,
+        noir_execute::{execute_program, parse_prover_inputs},
+        read_program_from_file, run_tests, save_program_to_file, save_witness_to_dir,
+        CompiledWorkspace, ProgramOutput, TestFilter,
+    },
+    oracle::OracleServer,
+    output::output_policy,
 };
-use noirc_driver::{CompileOptions, CompiledProgram, NOIR_ARTIFACT_VERSION_STRING};
 
-use crate::{exercise::TomlFile, nargo::{
-        cli_compile_workspace_full, compile, execute_program_and_decode, read_program_from_file, run_tests, save_witness_to_dir
-    }};
 
+// Name of the module the hidden grader tests are copied to
+const GRADER_MODULE: &str = "grader";
+
+// Name of the package the exercises are loaded into
+const RUNNER_CRATE_NAME: &str = "runner_crate";
+
+// Directory holding the workspace of every exercise, each with its own target directory for bb.
+// Kept apart from the other directories of noirlings (e.g. the cache), so no exercise name clashes.
+const WORKSPACES_DIR: &str = "target/noirlings/exercises";
+
+// File marking the workspace of an exercise as in use
+const LOCK_FILE: &str = ".lock";
 
-// Prepares testing crate
-// Copies the exercise file into testing crate
-pub fn prepare_crate_for_exercise(file_path: &PathBuf, prover_toml: Option<TomlFile>) -> PathBuf {
-    let crate_path = current_dir()
+// Name of the workspace members of library exercises
+const LIBRARY_MEMBER: &str = "library";
+const CONSUMER_MEMBER: &str = "consumer";
+
+// The workspace directory of an exercise, e.g. `target/noirlings/exercises/hashmaps1`
+pub fn exercise_workspace_dir(exercise: &Exercise) -> PathBuf {
+    workspace_dir(&exercise.name)
+}
+
+fn workspace_dir(exercise_name: &str) -> PathBuf {
+    current_dir()
         .unwrap()
-        .join(PathBuf::from("runner_crate"));
-    let src_dir = crate_path.join("src");
-    if !src_dir.exists() {
-        let _ = fs::create_dir(&src_dir);
+        .join(WORKSPACES_DIR)
+        .join(exercise_name)
+}
+
+// Where nargo writes the artifacts of the exercise, and bb reads them
+pub(crate) fn exercise_target_dir(exercise: &Exercise) -> PathBuf {
+    exercise_workspace_dir(exercise).join("target")
+}
+
+// Marks the workspaces used by a run as in use until dropped
+pub struct WorkspaceLock {
+    paths: Vec<PathBuf>,
+}
+
+impl Drop for WorkspaceLock {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
     }
-    let lib_path = src_dir.join("main.nr");
-    let file_path = current_dir().unwrap().join(file_path);
+}
 
-    match fs::copy(&file_path, &lib_path) {
-        Ok(_) => {}
-        Err(err) => panic!("Error occurred while preparing the exercise,\nExercise: {file_path:?}\nLib path: {lib_path:?}\n{err:?}"),
-    };
+// Takes the lock of the exercise workspace, and of the workspaces its derived inputs are read
+// from, so no other run rewrites their artifacts in the meantime.
+pub fn lock_exercise_workspace(exercise: &Exercise) -> anyhow::Result<WorkspaceLock> {
+    let mut lock = lock_workspace(&exercise.name)?;
+    if let Mode::BbVerify(options) = &exercise.mode {
+        for required_name in required_exercises(options) {
+            if required_name != exercise.name {
+                lock.paths.append(&mut lock_workspace(required_name)?.paths);
+            }
+        }
+    }
+    Ok(lock)
+}
 
-    if let Some(prover_toml) = prover_toml {
-        match prover_toml {
-            TomlFile::Inlined(str) => {
-                let prover_toml_path = crate_path.join(format!("{}.toml", PROVER_INPUT_FILE));
-                fs::write(prover_toml_path, str).expect("Unable to write file");
-            },
-            TomlFile::Path(path) => {
-                let prover_toml_path = crate_path.join(format!("{}.toml", PROVER_INPUT_FILE));
-                fs::copy(path, prover_toml_path).expect("Unable to copy file");
+// Takes the lock of a single workspace, holding the pid of the current process.
+// Fails while another live process holds it, a lock left behind by a dead one is taken over.
+fn lock_workspace(exercise_name: &str) -> anyhow::Result<WorkspaceLock> {
+    let crate_path = workspace_dir(exercise_name);
+    fs::create_dir_all(&crate_path)?;
+    let lock_path = crate_path.join(LOCK_FILE);
+
+    for _ in 0..2 {
+        match OpenOptions::new().write(true).create_new(true).open(&lock_path) {
+            Ok(mut lock_file) => {
+                write!(lock_file, "{}", std::process::id())?;
+                return Ok(WorkspaceLock {
+                    paths: vec![lock_path],
+                });
+            }
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                let owner = fs::read_to_string(&lock_path)
+                    .ok()
+                    .and_then(|pid| pid.trim().parse::<u32>().ok());
+                match owner {
+                    Some(pid) if !process_is_alive(pid) => {
+                        let _ = fs::remove_file(&lock_path);
+                    }
+                    _ => break,
+                }
             }
+            Err(err) => return Err(err.into()),
+        }
+    }
+    anyhow::bail!(
+        "The workspace of {exercise_name} is in use by another noirlings process.\nRemove {} if no other process is running.",
+        lock_path.display()
+    )
+}
+
+#[cfg(unix)]
+fn process_is_alive(pid: u32) -> bool {
+    Command::new("kill")
+        .arg("-0")
+        .arg(pid.to_string())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(true)
+}
+
+#[cfg(not(unix))]
+fn process_is_alive(_pid: u32) -> bool {
+    true
+}
+
+// The workspace of an exercise, with all of its sources loaded in memory
+struct ExerciseWorkspace {
+    workspace: Workspace,
+    file_manager: FileManager,
+    parsed_files: ParsedFiles,
+    // Serves the oracle mocks of the exercise, if it has any
+    oracle_server: Option<OracleServer>,
+}
+
+impl ExerciseWorkspace {
+    // The foreign call resolver of the executions and the tests
+    fn oracle_resolver_url(&self) -> Option<&str> {
+        self.oracle_server.as_ref().map(OracleServer::url)
+    }
+}
+
+// Loads the exercise (and its grader tests, if any) into an in-memory workspace.
+// Only the vendored dependencies are read from their directories, nothing is written:
+// the workspace directory just receives the artifacts needed by bb.
+fn load_exercise_workspace(exercise: &Exercise) -> anyhow::Result<ExerciseWorkspace> {
+    let root_dir = exercise_workspace_dir(exercise);
+    let mut file_manager = file_manager_with_stdlib(&root_dir);
+    let dependencies = load_dependencies(exercise, &mut file_manager)?;
+
+    let members = match &exercise.mode {
+        Mode::Library(options) => {
+            let library = in_memory_package(
+                &options.name,
+                PackageType::Library,
+                root_dir.join(LIBRARY_MEMBER),
+                "lib.nr",
+                dependencies.clone(),
+            )?;
+            add_sources(&mut file_manager, &exercise.path, &library.entry_path, "")?;
+
+            let mut consumer_dependencies = dependencies;
+            consumer_dependencies.insert(
+                crate_name(&options.name)?,
+                Dependency::Local { package: library.clone() },
+            );
+            let consumer = in_memory_package(
+                CONSUMER_MEMBER,
+                PackageType::Library,
+                root_dir.join(CONSUMER_MEMBER),
+                "lib.nr",
+                consumer_dependencies,
+            )?;
+            add_sources(&mut file_manager, &options.consumer, &consumer.entry_path, "")?;
+            vec![library, consumer]
+        }
+        _ => {
+            let package_type = match exercise.mode {
+                Mode::Contract(_) => PackageType::Contract,
+                _ => PackageType::Binary,
+            };
+            let package = in_memory_package(
+                RUNNER_CRATE_NAME,
+                package_type,
+                root_dir.clone(),
+                "main.nr",
+                dependencies,
+            )?;
+
+            match &exercise.grader {
+                Some(grader) => {
+                    let grader_module = format!("\nmod {GRADER_MODULE};\n");
+                    add_sources(&mut file_manager, &exercise.path, &package.entry_path, &grader_module)?;
+                    let grader_path = root_dir.join("src").join(format!("{GRADER_MODULE}.nr"));
+                    add_sources(&mut file_manager, grader, &grader_path, "")?;
+                }
+                None => add_sources(&mut file_manager, &exercise.path, &package.entry_path, "")?,
+            }
+            vec![package]
+        }
+    };
+
+    let workspace = Workspace {
+        root_dir,
+        target_dir: None,
+        members,
+        selected_package_index: None,
+        is_assumed: false,
+    };
+    let parsed_files = parse_all(&file_manager);
+    // Only the modes running the circuit or its tests call oracles
+    let oracle_server = if exercise.oracles.is_empty() || !exercise.mode.executes() {
+        None
+    } else {
+        Some(OracleServer::start(&exercise.oracles)?)
+    };
+    Ok(ExerciseWorkspace {
+        workspace,
+        file_manager,
+        parsed_files,
+        oracle_server,
+    })
+}
+
+// A package whose sources live in the file manager, under `root_dir/src`
+fn in_memory_package(
+    name: &str,
+    package_type: PackageType,
+    root_dir: PathBuf,
+    entry_file: &str,
+    dependencies: BTreeMap<CrateName, Dependency>,
+) -> anyhow::Result<Package> {
+    Ok(Package {
+        version: None,
+        compiler_required_version: None,
+        entry_path: root_dir.join("src").join(entry_file),
+        root_dir,
+        package_type,
+        name: crate_name(name)?,
+        dependencies,
+        expression_width: None,
+    })
+}
+
+fn crate_name(name: &str) -> anyhow::Result<CrateName> {
+    name.parse()
+        .map_err(|err: String| anyhow::anyhow!("Invalid crate name `{name}`: {err}"))
+}
+
+// Resolves the vendored dependencies of the exercise from their Nargo.toml and loads their sources.
+// They are declared relative to the noirlings directory.
+fn load_dependencies(
+    exercise: &Exercise,
+    file_manager: &mut FileManager,
+) -> anyhow::Result<BTreeMap<CrateName, Dependency>> {
+    let mut dependencies = BTreeMap::new();
+    for (name, path) in &exercise.dependencies {
+        let toml_path = get_package_manifest(&current_dir()?.join(path))?;
+        let workspace = resolve_workspace_from_toml(
+            &toml_path,
+            PackageSelection::DefaultOrAll,
+            Some(NOIR_ARTIFACT_VERSION_STRING.to_string()),
+        )?;
+        insert_all_files_for_workspace_into_file_manager(&workspace, file_manager);
+
+        let Some(package) = workspace.members.into_iter().next() else {
+            anyhow::bail!("The dependency `{name}` at {} has no package", path.display());
+        };
+        dependencies.insert(crate_name(name)?, Dependency::Local { package });
+    }
+    Ok(dependencies)
+}
+
+// Adds the sources found at `sources_path` to the file manager, as the `src` directory of a package.
+// A single file becomes the entry file, a directory is mapped as a whole.
+// `entry_suffix` is appended to the entry file, e.g. to declare the grader module.
+fn add_sources(
+    file_manager: &mut FileManager,
+    sources_path: &Path,
+    entry_path: &Path,
+    entry_suffix: &str,
+) -> anyhow::Result<()> {
+    let files = if sources_path.is_dir() {
+        let src_dir = entry_path.parent().expect("The entry file lives in the src directory");
+        let pattern = sources_path.join("**").join("*.nr");
+        glob(&pattern.to_string_lossy())?
+            .filter_map(Result::ok)
+            .map(|file| {
+                let relative_path = file.strip_prefix(sources_path).unwrap_or(&file).to_path_buf();
+                (file, src_dir.join(relative_path))
+            })
+            .collect()
+    } else {
+        vec![(sources_path.to_path_buf(), entry_path.to_path_buf())]
+    };
 
+    for (file, path) in files {
+        let mut source = fs::read_to_string(&file).map_err(|err| {
+            anyhow::anyhow!("Error occurred while loading the exercise,\nFile: {file:?}\n{err}")
+        })?;
+        if path == entry_path {
+            source.push_str(entry_suffix);
         }
+        file_manager.add_file_with_source(&path, source);
+    }
+    Ok(())
+}
+
+// Whether a bb step follows the execution, reading the program and witness artifacts from disk
+pub(crate) fn needs_bb_artifacts(exercise: &Exercise) -> bool {
+    matches!(exercise.mode, Mode::BbProve(_) | Mode::BbVerify(_))
+}
+
+// Recursively copies the content of `from` into `to`, preserving its structure
+pub(crate) fn copy_dir_all(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+// Maps the compiler settings of the exercise onto nargo's compile options
+fn compile_options(exercise: &Exercise) -> CompileOptions {
+    let compiler = &exercise.compiler;
+    CompileOptions {
+        deny_warnings: compiler.deny_warnings,
+        silence_warnings: compiler.silence_warnings,
+        force_brillig: compiler.force_brillig,
+        skip_underconstrained_check: compiler.skip_underconstrained_check,
+        expression_width: compiler.expression_width.map(|width| match width {
+            0 => ExpressionWidth::Unbounded,
+            width => ExpressionWidth::Bounded { width },
+        }),
+        ..CompileOptions::default()
     }
-    crate_path
 }
 
 // Builds the testing crate with scarb
-pub fn nargo_compile(file_path: &PathBuf) -> anyhow::Result<String> {
-    let _: PathBuf = prepare_crate_for_exercise(file_path, None);
-    match compile() {
-        Ok(_) => Ok("".into()),
+pub fn nargo_compile(exercise: &Exercise) -> anyhow::Result<String> {
+    let exercise_workspace = load_exercise_workspace(exercise)?;
+
+    let compiled = match compile_exercise_workspace(exercise, &exercise_workspace) {
+        Ok(compiled) => compiled,
         Err(err) => anyhow::bail!("Failed to compile the program: {:?}", err),
+    };
+    check_circuit_size(exercise, &compiled)?;
+    Ok("".into())
+}
+
+// Compiles the programs and contracts of the workspace with the compiler options of the exercise
+fn compile_exercise_workspace(
+    exercise: &Exercise,
+    exercise_workspace: &ExerciseWorkspace,
+) -> anyhow::Result<CompiledWorkspace> {
+    cli_compile_workspace_full(
+        &exercise_workspace.file_manager,
+        &exercise_workspace.parsed_files,
+        &exercise_workspace.workspace,
+        &compile_options(exercise),
+    )
+}
+
+// Checks the opcode counts of the compiled programs against the budget of the exercise.
+// Prints a per-function breakdown whenever a budget is set.
+fn check_circuit_size(exercise: &Exercise, compiled: &CompiledWorkspace) -> anyhow::Result<()> {
+    if exercise.max_opcodes.is_none() && exercise.max_brillig_opcodes.is_none() {
+        return Ok(());
     }
+
+    for (package, program) in &compiled.programs {
+        let program = ProgramArtifact::from(program.clone());
+
+        report!("[{}] Circuit size:", package.name);
+        let mut acir_opcodes = 0;
+        for (index, circuit) in program.bytecode.functions.iter().enumerate() {
+            let name = program.names.get(index).cloned().unwrap_or_else(|| format!("function {index}"));
+            report!("    {name:<30} {:>8} ACIR opcodes", circuit.opcodes.len());
+            acir_opcodes += circuit.opcodes.len();
+        }
+        let mut brillig_opcodes = 0;
+        for (index, brillig) in program.bytecode.unconstrained_functions.iter().enumerate() {
+            let name = program
+                .brillig_names
+                .get(index)
+                .cloned()
+                .unwrap_or_else(|| format!("unconstrained {index}"));
+            report!("    {name:<30} {:>8} Brillig opcodes", brillig.bytecode.len());
+            brillig_opcodes += brillig.bytecode.len();
+        }
+
+        let mut over_budget = Vec::new();
+        if let Some(max_opcodes) = exercise.max_opcodes {
+            report!("    {:<30} {:>8} / {max_opcodes} ACIR opcodes", "total", acir_opcodes);
+            if acir_opcodes > max_opcodes {
+                over_budget.push(format!("{acir_opcodes} ACIR opcodes (budget: {max_opcodes})"));
+            }
+        }
+        if let Some(max_brillig_opcodes) = exercise.max_brillig_opcodes {
+            report!(
+                "    {:<30} {:>8} / {max_brillig_opcodes} Brillig opcodes",
+                "total", brillig_opcodes
+            );
+            if brillig_opcodes > max_brillig_opcodes {
+                over_budget.push(format!(
+                    "{brillig_opcodes} Brillig opcodes (budget: {max_brillig_opcodes})"
+                ));
+            }
+        }
+
+        if !over_budget.is_empty() {
+            anyhow::bail!(
+                "[{}] The circuit is too big: {}",
+                package.name,
+                over_budget.join(", ")
+            );
+        }
+    }
+    Ok(())
 }
 
 // Execute the crate with noir
-pub fn nargo_execute(
-    file_path: &PathBuf,
+// When the exercise sets `expected_return` it takes precedence over the `return` key of the Prover.toml
+pub fn nargo_execute(exercise: &Exercise, prover_toml: TomlFile) -> anyhow::Result<String> {
+    with_output(exercise, |output| {
+        let exercise_workspace = load_exercise_workspace(exercise)?;
+        let workspace = &exercise_workspace.workspace;
+        let target_dir = &workspace.target_directory_path();
+
+        let compiled = compile_exercise_workspace(exercise, &exercise_workspace)?;
+        check_circuit_size(exercise, &compiled)?;
+
+        let prover_toml = prover_toml.to_string();
+        for (package, program) in &compiled.programs {
+            let (return_value, prover_return, witness_stack) = execute_program_and_decode(
+                program,
+                &prover_toml,
+                output,
+                exercise_workspace.oracle_resolver_url(),
+                Some(workspace.root_dir.clone()),
+                Some(package.name.to_string()),
+            )?;
+
+            report!("[{}] Circuit witness successfully solved", package.name);
+            if let Some(return_value) = &return_value {
+                report!("[{}] Circuit output: {return_value:?}", package.name);
+            }
+
+            let expected_return = match &exercise.expected_return {
+                Some(expected) => Some(parse_expected_return(expected, &program.abi)?),
+                None => prover_return,
+            };
+            check_return_value(expected_return.as_ref(), return_value.as_ref())?;
+
+            // bb reads the program and the witness from the target directory
+            if needs_bb_artifacts(exercise) {
+                save_program_to_file(&program.clone().into(), &package.name, target_dir);
+                let witness_name = &exercise.name;
+                let witness_path = save_witness_to_dir(witness_stack, witness_name, target_dir)?;
+                report!(
+                    "[{}] Witness saved to {}",
+                    package.name,
+                    witness_path.display()
+                );
+            }
+        }
+        Ok(())
+    })
+}
+
+// Runs the executions or the tests of the exercise, handling what they print according to
+// its output policy. Returns the captured output, which is also added to the error on failure.
+fn with_output(
+    exercise: &Exercise,
+    run: impl FnOnce(&mut ProgramOutput) -> anyhow::Result<()>,
+) -> anyhow::Result<String> {
+    let mut output = ProgramOutput::new(output_policy(exercise));
+    match run(&mut output) {
+        Ok(()) => Ok(output.into_captured()),
+        Err(error) if output.captured().is_empty() => Err(error),
+        Err(error) => {
+            let message = format!("{error}\nOutput:\n{}", output.captured());
+            Err(error.context(message))
+        }
+    }
+}
+
+// Compiles the crate expecting an error whose message contains `expected`
+pub fn nargo_compile_fail(exercise: &Exercise, expected: &str) -> anyhow::Result<String> {
+    let exercise_workspace = load_exercise_workspace(exercise)?;
+
+    let Some(errors) = cli_compile_workspace_errors(
+        &exercise_workspace.file_manager,
+        &exercise_workspace.parsed_files,
+        &exercise_workspace.workspace,
+        &compile_options(exercise),
+    ) else {
+        anyhow::bail!("The program compiled, but it should fail with the error: \"{expected}\"");
+    };
+    if !errors.iter().any(|error| error.contains(expected)) {
+        anyhow::bail!(
+            "The program failed to compile, but not with the expected error\n  Expected: {expected}\n  Found:    {}",
+            errors.join("\n            ")
+        );
+    }
+    report!("Compilation failed as expected");
+    Ok("".into())
+}
+
+// Executes the crate expecting the circuit to fail with a message containing `expected`
+pub fn nargo_execute_fail(
+    exercise: &Exercise,
     prover_toml: TomlFile,
-    exercise_name: String,
+    expected: &str,
+) -> anyhow::Result<String> {
+    with_output(exercise, |output| {
+        let exercise_workspace = load_exercise_workspace(exercise)?;
+        let workspace = &exercise_workspace.workspace;
+
+        let compiled = compile_exercise_workspace(exercise, &exercise_workspace)?;
+        check_circuit_size(exercise, &compiled)?;
+
+        let prover_toml = prover_toml.to_string();
+        for (package, program) in &compiled.programs {
+            let error = match execute_program_and_decode(
+                program,
+                &prover_toml,
+                output,
+                exercise_workspace.oracle_resolver_url(),
+                Some(workspace.root_dir.clone()),
+                Some(package.name.to_string()),
+            ) {
+                Ok(_) => anyhow::bail!(
+                    "[{}] The circuit was solved, but it should fail with: \"{expected}\"",
+                    package.name
+                ),
+                Err(error) => error,
+            };
+
+            // Errors which don't come from the circuit itself (e.g. invalid inputs) are real failures
+            let Some(message) = circuit_failure_message(&error, &program.abi.error_types) else {
+                return Err(error);
+            };
+            if !message.contains(expected) {
+                anyhow::bail!(
+                    "[{}] The circuit failed, but not with the expected message\n  Expected: {expected}\n  Found:    {message}",
+                    package.name
+                );
+            }
+            report!("[{}] Circuit failed as expected: {message}", package.name);
+        }
+        Ok(())
+    })
+}
+
+// The outcome of a single case of a table-driven execute exercise
+struct CaseReport {
+    name: String,
+    label: Option<String>,
+    passed: bool,
+    details: String,
+}
+
+// Executes every case against a single compilation of the crate and prints a report table
+pub fn nargo_execute_cases(exercise: &Exercise, cases: &[ExecuteCase]) -> anyhow::Result<String> {
+    with_output(exercise, |output| {
+        let exercise_workspace = load_exercise_workspace(exercise)?;
+        let workspace = &exercise_workspace.workspace;
+
+        let compiled = compile_exercise_workspace(exercise, &exercise_workspace)?;
+        check_circuit_size(exercise, &compiled)?;
+
+        for (package, program) in &compiled.programs {
+            let reports = cases
+                .iter()
+                .map(|case| {
+                    let (passed, details) = match run_execute_case(
+                        program,
+                        case,
+                        output,
+                        exercise_workspace.oracle_resolver_url(),
+                        Some(workspace.root_dir.clone()),
+                        Some(package.name.to_string()),
+                    ) {
+                        Ok(details) => (true, details),
+                        Err(error) => (false, error.to_string()),
+                    };
+                    CaseReport {
+                        name: case.name.clone(),
+                        label: case.label.clone(),
+                        passed,
+                        details,
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            print_case_reports(&package.name.to_string(), &reports);
+
+            let count_failed = reports.iter().filter(|report| !report.passed).count();
+            if count_failed > 0 {
+                let plural = if count_failed == 1 { "" } else { "s" };
+                anyhow::bail!("[{}] {count_failed} case{plural} failed", package.name);
+            }
+        }
+        Ok(())
+    })
+}
+
+// Runs a single case, returning a short description of the outcome when it matches the expectation
+fn run_execute_case(
+    program: &CompiledProgram,
+    case: &ExecuteCase,
+    output: &mut ProgramOutput,
+    foreign_call_resolver_url: Option<&str>,
+    root_path: Option<PathBuf>,
+    package_name: Option<String>,
 ) -> anyhow::Result<String> {
-    /*      Small version example
-    let path = prepare_crate_for_exercise(file_path, Some(prover_toml));
-    let witness_stack = run().unwrap();
-
-    let witness_name = &exercise_name;
-    let witness_path = save_witness_to_dir(witness_stack, witness_name, target_dir)?;
-    println!(
-        "[{}] Witness saved to {}",
-        package.name,
-        witness_path.display()
+    let (inputs_map, prover_return) = parse_prover_inputs(&case.inputs.to_string(), &program.abi)?;
+
+    let execution = execute_program(
+        program,
+        &inputs_map,
+        output,
+        foreign_call_resolver_url,
+        root_path,
+        package_name,
     );
 
-    Ok("".into())
-    */
-
-    let crate_path = prepare_crate_for_exercise(file_path, Some(prover_toml));
-    let toml_path = get_package_manifest(&crate_path)?;
-    let workspace = resolve_workspace_from_toml(
-        &toml_path,
-        PackageSelection::DefaultOrAll,
-        Some(NOIR_ARTIFACT_VERSION_STRING.to_string()),
-    )?;
-    let target_dir = &workspace.target_directory_path();
-
-    // Compile the full workspace in order to generate any build artifacts.
-    let default_options = CompileOptions::default();
-    cli_compile_workspace_full(&workspace, &default_options)?;
-
-    let binary_packages = workspace.into_iter().filter(|package| package.is_binary());
-    for package in binary_packages {
-        let program_artifact_path = workspace.package_build_path(package);
-        let program: CompiledProgram =
-            read_program_from_file(program_artifact_path.clone())?.into();
-
-        let (return_value, witness_stack) = execute_program_and_decode(
-            program,
-            package,
-            PROVER_INPUT_FILE,
-            None,
-            Some(workspace.root_dir.clone()),
-            Some(package.name.to_string()),
-        )?;
+    match (&case.expected_failure, execution) {
+        (Some(expected), Ok(_)) => {
+            anyhow::bail!("The circuit was solved, but it should fail with: \"{expected}\"")
+        }
+        (Some(expected), Err(error)) => {
+            let Some(message) = circuit_failure_message(&error, &program.abi.error_types) else {
+                return Err(error);
+            };
+            if !message.contains(expected) {
+                anyhow::bail!("Failed with \"{message}\" instead of \"{expected}\"");
+            }
+            Ok(format!("failed with \"{message}\""))
+        }
+        (None, Err(error)) => {
+            let message = circuit_failure_message(&error, &program.abi.error_types)
+                .unwrap_or_else(|| error.to_string());
+            anyhow::bail!("Execution failed: {message}")
+        }
+        (None, Ok(witness_stack)) => {
+            let main_witness = &witness_stack
+                .peek()
+                .expect("Should have at least one witness on the stack")
+                .witness;
+            let (_, return_value) = program.abi.decode(main_witness)?;
 
-        println!("[{}] Circuit witness successfully solved", package.name);
-        if let Some(return_value) = return_value {
-            println!("[{}] Circuit output: {return_value:?}", package.name);
+            let expected_return = match &case.expected_return {
+                Some(expected) => Some(parse_expected_return(expected, &program.abi)?),
+                None => prover_return,
+            };
+            check_return_value(expected_return.as_ref(), return_value.as_ref())?;
+            match return_value {
+                Some(return_value) => Ok(format!("returned {return_value:?}")),
+                None => Ok("solved".to_string()),
+            }
         }
+    }
+}
 
-        let witness_name = &exercise_name;
-        let witness_path = save_witness_to_dir(witness_stack, witness_name, target_dir)?;
-        println!(
-            "[{}] Witness saved to {}",
-            package.name,
-            witness_path.display()
-        );
+fn print_case_reports(package_name: &str, reports: &[CaseReport]) {
+    let name_width = reports
+        .iter()
+        .map(|report| report.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("Case".len());
+
+    report!("[{package_name}] {:<name_width$}  {:<6}  Details", "Case", "Status");
+    for report in reports {
+        let status = if report.passed {
+            style(format!("{:<6}", "ok")).green()
+        } else {
+            style(format!("{:<6}", "FAIL")).red()
+        };
+        let details = match &report.label {
+            Some(label) => format!("({label}) {}", report.details),
+            None => report.details.clone(),
+        };
+        report!("[{package_name}] {:<name_width$}  {status}  {details}", report.name);
     }
-    anyhow::Ok("".into())
 }
 
-pub fn bb_prove(exercise_name: String) -> anyhow::Result<String> {
-    // -b ./target/hello_world.json -w ./target/witness-name.gz -o ./target/proof-name
-    println!("Creating proof with barretenberg");
-    let output = Command::new("bb")
-        .arg("prove")
-        .arg("-b")
-        .arg("runner_crate/target/runner_crate.json")
-        .arg("-w")
-        .arg(format!("runner_crate/target/{}.gz", exercise_name))
-        .arg("-o")
-        .arg(format!("runner_crate/target/proof-{}", exercise_name))
-        .output()?;
-    if !output.status.success() {
+// Returns the failure message of a circuit execution error,
+// or `None` if the error didn't come from solving the circuit
+fn circuit_failure_message(
+    error: &anyhow::Error,
+    error_types: &BTreeMap<ErrorSelector, AbiErrorType>,
+) -> Option<String> {
+    let nargo_error = error.downcast_ref::<NargoError<FieldElement>>()?;
+    Some(
+        nargo_error
+            .user_defined_failure_message(error_types)
+            .unwrap_or_else(|| nargo_error.to_string()),
+    )
+}
+
+// Compiles the crate as a contract and checks the functions of the saved contract artifact
+pub fn nargo_contract(exercise: &Exercise, options: &ContractOptions) -> anyhow::Result<String> {
+    let exercise_workspace = load_exercise_workspace(exercise)?;
+
+    let compiled = compile_exercise_workspace(exercise, &exercise_workspace)?;
+
+    let Some(contract) = compiled
+        .contracts
+        .into_iter()
+        .find(|contract| contract.name == options.name)
+    else {
+        anyhow::bail!("No contract named `{}` was compiled", options.name);
+    };
+    let contract = ContractArtifact::from(contract);
+
+    let mut problems = Vec::new();
+    for spec in &options.functions {
+        let Some(function) = contract.functions.iter().find(|function| function.name == spec.name)
+        else {
+            problems.push(format!("Missing function `{}`", spec.name));
+            continue;
+        };
+
+        if let Some(unconstrained) = spec.unconstrained {
+            if function.is_unconstrained != unconstrained {
+                let expected = if unconstrained { "unconstrained" } else { "constrained" };
+                problems.push(format!("Function `{}` should be {expected}", spec.name));
+            }
+        }
+        if let Some(parameters) = &spec.parameters {
+            let found: Vec<&str> = function
+                .abi
+                .parameters
+                .iter()
+                .map(|parameter| parameter.name.as_str())
+                .collect();
+            if found != *parameters {
+                problems.push(format!(
+                    "Function `{}` should take the parameters {parameters:?}, found {found:?}",
+                    spec.name
+                ));
+            }
+        }
+        if let Some(returns) = spec.returns {
+            if function.abi.return_type.is_some() != returns {
+                let expected = if returns { "return a value" } else { "not return anything" };
+                problems.push(format!("Function `{}` should {expected}", spec.name));
+            }
+        }
+    }
+
+    if !problems.is_empty() {
         anyhow::bail!(
-            "Failed to prove the program: {}",
-            String::from_utf8_lossy(&output.stderr)
+            "The contract `{}` doesn't match the expected interface:\n  {}",
+            options.name,
+            problems.join("\n  ")
         );
-    } else{
-        anyhow::Ok("".into())
-    }
-}
-
-pub fn bb_prove_verify_saving_files(exercise_name: String) -> anyhow::Result<String> {
-    
-    bb_prove(exercise_name.clone())?;
-
-    // bb write_vk -b ./target/hello_world.json -o ./target/vk
-    // bb verify -k ./target/vk -p ./target/proof
-    println!("Exporting verification key with barretenberg (bb)");
-    let output_write_vk = Command::new("bb")
-        .arg("write_vk")
-        .arg("-b")
-        .arg("runner_crate/target/runner_crate.json")
-        .arg("-o")
-        .arg(format!("runner_crate/target/vk-{}", exercise_name))
-        .output()?;
-    println!("Verifying proof with barretenberg (bb)");
-    let output_verify = Command::new("bb")
-        .arg("verify")
-        .arg("-k")
-        .arg(format!("runner_crate/target/vk-{}", exercise_name))
-        .arg("-p")
-        .arg(format!("runner_crate/target/proof-{}", exercise_name))
-        .output()?;
-    if !output_write_vk.status.success() {
+    }
+    report!(
+        "[{RUNNER_CRATE_NAME}] Contract `{}` compiled with {} functions",
+        contract.name,
+        contract.functions.len()
+    );
+    Ok("".into())
+}
+
+// Parses an `expected_return` value from info.toml using the return type of the circuit ABI
+fn parse_expected_return(expected: &toml::Value, abi: &Abi) -> anyhow::Result<InputValue> {
+    let mut table = toml::value::Table::new();
+    table.insert(MAIN_RETURN_NAME.to_string(), expected.clone());
+    let return_abi = Abi {
+        parameters: Vec::new(),
+        ..abi.clone()
+    };
+
+    let mut input_map = Format::Toml.parse(&toml::to_string(&table)?, &return_abi)?;
+    match input_map.remove(MAIN_RETURN_NAME) {
+        Some(value) => Ok(value),
+        None => anyhow::bail!("An expected return value was given but the circuit does not return anything"),
+    }
+}
+
+// Compares the decoded circuit output against the expected one, if any
+fn check_return_value(
+    expected: Option<&InputValue>,
+    actual: Option<&InputValue>,
+) -> anyhow::Result<()> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    if Some(expected) == actual {
+        return Ok(());
+    }
+
+    let actual = match actual {
+        Some(actual) => format!("{actual:?}"),
+        None => "nothing".to_string(),
+    };
+    anyhow::bail!(
+        "The circuit returned an unexpected value\n  Expected: {expected:?}\n  Returned: {actual}"
+    )
+}
+
+// The files the proving backend reads and writes for the exercise
+pub(crate) fn backend_artifacts(exercise: &Exercise) -> BackendArtifacts {
+    BackendArtifacts::new(
+        &exercise_target_dir(exercise),
+        RUNNER_CRATE_NAME,
+        &exercise.name,
+    )
+}
+
+// The artifacts of the proof of a required exercise, converted to fields next to the
+// artifacts of the exercise deriving its inputs from them
+fn required_artifacts(exercise: &Exercise, required_name: &str) -> BackendArtifacts {
+    let converted = BackendArtifacts::new(
+        &exercise_target_dir(exercise),
+        RUNNER_CRATE_NAME,
+        required_name,
+    );
+    BackendArtifacts {
+        vk_fields: converted.vk_fields,
+        proof_fields: converted.proof_fields,
+        ..BackendArtifacts::new(
+            &workspace_dir(required_name).join("target"),
+            RUNNER_CRATE_NAME,
+            required_name,
+        )
+    }
+}
+
+// The exercises the derived inputs are taken from
+fn required_exercises(options: &BbVerifyOptions) -> BTreeSet<&str> {
+    options
+        .derived_inputs
+        .values()
+        .map(|derived_input| derived_input.from.as_str())
+        .collect()
+}
+
+// The files the derived inputs of the exercise are computed from
+pub(crate) fn derived_input_files(exercise: &Exercise, options: &BbVerifyOptions) -> Vec<PathBuf> {
+    required_exercises(options)
+        .into_iter()
+        .flat_map(|required_name| {
+            let artifacts = required_artifacts(exercise, required_name);
+            [artifacts.program, artifacts.vk, artifacts.proof]
+        })
+        .collect()
+}
+
+// The verification key and the proof of a required exercise, as fields
+struct ProofAsFields {
+    // The hash of the key comes first
+    vk: Vec<String>,
+    // The public inputs come first
+    proof: Vec<String>,
+    public_inputs_count: usize,
+}
+
+impl ProofAsFields {
+    fn input_value(&self, artifact: DerivedArtifact) -> toml::Value {
+        let array = |fields: &[String]| {
+            toml::Value::Array(fields.iter().cloned().map(toml::Value::String).collect())
+        };
+        match artifact {
+            DerivedArtifact::VkFields => array(&self.vk[1..]),
+            DerivedArtifact::VkHash => toml::Value::String(self.vk[0].clone()),
+            DerivedArtifact::ProofFields => array(&self.proof[self.public_inputs_count..]),
+            DerivedArtifact::PublicInputs => array(&self.proof[..self.public_inputs_count]),
+        }
+    }
+}
+
+fn proof_as_fields(exercise: &Exercise, required_name: &str) -> anyhow::Result<ProofAsFields> {
+    let artifacts = required_artifacts(exercise, required_name);
+    if !artifacts.vk.exists() || !artifacts.proof.exists() {
         anyhow::bail!(
-            "Failed to verify the program: {}",
-            String::from_utf8_lossy(&output_write_vk.stderr)
+            "No proof of {required_name} was found, solve it first (saving its files) to derive the inputs of {exercise}"
         );
-    } else if !output_verify.status.success() {
+    }
+
+    let backend = proving_backend()?;
+    report!(
+        "Converting the proof of {required_name} to fields with {}",
+        backend.name()
+    );
+    fs::create_dir_all(exercise_target_dir(exercise))?;
+    backend.vk_as_fields(&artifacts)?;
+    backend.proof_as_fields(&artifacts)?;
+
+    let vk: Vec<String> = serde_json::from_slice(&fs::read(&artifacts.vk_fields)?)?;
+    let proof: Vec<String> = serde_json::from_slice(&fs::read(&artifacts.proof_fields)?)?;
+    let program = read_program_from_file(&artifacts.program)?;
+    let public_inputs_count = program.bytecode.functions[0].public_inputs().0.len();
+    if vk.is_empty() || proof.len() < public_inputs_count {
+        anyhow::bail!("The proof of {required_name} couldn't be converted to fields");
+    }
+    Ok(ProofAsFields {
+        vk,
+        proof,
+        public_inputs_count,
+    })
+}
+
+/// The inputs of a bb exercise: its toml file, completed with the inputs derived from
+/// the proofs of the exercises it requires. The derived inputs to check are the learner's
+/// to give, they must match the derived values.
+pub fn bb_prover_toml(exercise: &Exercise, options: &BbVerifyOptions) -> anyhow::Result<TomlFile> {
+    if options.derived_inputs.is_empty() {
+        return Ok(options.toml_file.clone());
+    }
+
+    let mut inputs: toml::value::Table = toml::from_str(&options.toml_file.to_string())?;
+    let mut converted_proofs: BTreeMap<&str, ProofAsFields> = BTreeMap::new();
+    for (input_name, derived_input) in &options.derived_inputs {
+        let required_name = derived_input.from.as_str();
+        if !converted_proofs.contains_key(required_name) {
+            converted_proofs.insert(required_name, proof_as_fields(exercise, required_name)?);
+        }
+        let derived_value = converted_proofs[required_name].input_value(derived_input.artifact);
+        if derived_input.check {
+            check_derived_input(
+                input_name,
+                derived_input,
+                inputs.get(input_name),
+                &derived_value,
+            )?;
+        } else {
+            inputs.insert(input_name.clone(), derived_value);
+        }
+    }
+    Ok(TomlFile::Inlined(toml::to_string(&toml::Value::Table(
+        inputs,
+    ))?))
+}
+
+// Fails unless the learner gave the input the value derived from the proof
+fn check_derived_input(
+    input_name: &str,
+    derived_input: &DerivedInput,
+    given_value: Option<&toml::Value>,
+    derived_value: &toml::Value,
+) -> anyhow::Result<()> {
+    let Some(given_value) = given_value else {
         anyhow::bail!(
-            "Failed to verify the program: {}",
-            String::from_utf8_lossy(&output_verify.stderr)
+            "The input `{input_name}` is missing: give it the {} of the proof of {}",
+            derived_input.artifact,
+            derived_input.from
         );
-        
-    }else{
-        anyhow::Ok("".into())
-    }
-}
-
-pub fn bb_prove_and_verify(exercise_name: String) -> anyhow::Result<String> {
-    // more info https://github.com/AztecProtocol/aztec-packages/blob/barretenberg-v0.55.0/barretenberg/cpp/src/barretenberg/bb/main.cpp#L1369-L1512
-    // prove_and_verify -b ./target/hello_world.json -w ./target/witness-name.gz
-    println!("Proving and verifying proof with barretenberg (bb)");
-    let output_verify = Command::new("bb")
-        .arg("prove_and_verify")
-        .arg("-b")
-        .arg("runner_crate/target/runner_crate.json")
-        .arg("-w")
-        .arg(format!("runner_crate/target/{}.gz", exercise_name))
-        .output()?;
-    if !output_verify.status.success() {
+    };
+    let given_fields = toml_fields(given_value);
+    if given_fields.is_none() || given_fields != toml_fields(derived_value) {
         anyhow::bail!(
-            "Failed to prove and verify the program: {}",
-            String::from_utf8_lossy(&output_verify.stderr)
+            "The input `{input_name}` doesn't hold the {} of the proof of {}",
+            derived_input.artifact,
+            derived_input.from
         );
-        
-    }else{
-        anyhow::Ok("".into())
     }
+    Ok(())
 }
 
-// Runs tests on the testing crate with nargo
-pub fn nargo_test(file_path: &PathBuf) -> anyhow::Result<String> {
-    let crate_path = prepare_crate_for_exercise(file_path, None);
-    let toml_path = get_package_manifest(&crate_path)?;
-    let workspace = resolve_workspace_from_toml(
-        &toml_path,
-        PackageSelection::DefaultOrAll,
-        Some(NOIR_ARTIFACT_VERSION_STRING.to_string()),
-    )?;
+// The field elements of an input value, whether written in decimal or hexadecimal
+fn toml_fields(value: &toml::Value) -> Option<Vec<FieldElement>> {
+    match value {
+        toml::Value::String(field) => FieldElement::try_from_str(field).map(|field| vec![field]),
+        toml::Value::Integer(field) => Some(vec![FieldElement::from(*field as i128)]),
+        toml::Value::Array(values) => values
+            .iter()
+            .map(toml_fields)
+            .collect::<Option<Vec<_>>>()
+            .map(|fields| fields.concat()),
+        _ => None,
+    }
+}
+
+pub fn bb_prove(exercise: &Exercise) -> anyhow::Result<String> {
+    let backend = proving_backend()?;
+    report!("Creating proof with {}", backend.name());
+    backend.prove(&backend_artifacts(exercise))?;
+    anyhow::Ok("".into())
+}
+
+pub fn bb_prove_verify_saving_files(exercise: &Exercise) -> anyhow::Result<String> {
+    let backend = proving_backend()?;
+    let artifacts = backend_artifacts(exercise);
+    report!("Creating proof with {}", backend.name());
+    backend.prove(&artifacts)?;
+    report!("Exporting verification key with {}", backend.name());
+    backend.write_vk(&artifacts)?;
+    report!("Verifying proof with {}", backend.name());
+    backend.verify(&artifacts)?;
+    anyhow::Ok("".into())
+}
+
+pub fn bb_prove_and_verify(exercise: &Exercise) -> anyhow::Result<String> {
+    let backend = proving_backend()?;
+    report!("Proving and verifying proof with {}", backend.name());
+    backend.prove_and_verify(&backend_artifacts(exercise))?;
+    anyhow::Ok("".into())
+}
+
+// Runs the tests of the hidden consumer package against the learner's library
+pub fn nargo_library(exercise: &Exercise) -> anyhow::Result<String> {
+    with_output(exercise, |output| {
+        let exercise_workspace = load_exercise_workspace(exercise)?;
 
-    let mut workspace_file_manager = workspace.new_file_manager();
-    insert_all_files_for_workspace_into_file_manager(&workspace, &mut workspace_file_manager);
-    let parsed_files = parse_all(&workspace_file_manager);
+        let test_report = test_workspace_package(
+            &exercise_workspace,
+            CONSUMER_MEMBER,
+            &compile_options(exercise),
+            &TestFilter::Anything,
+            output,
+        )?;
+        if test_report.iter().any(|(_, status)| status.failed()) {
+            anyhow::bail!("Some consumer tests failed");
+        } else {
+            Ok(())
+        }
+    })
+}
 
-    let pattern = FunctionNameMatch::Anything;
+// Runs the tests of a single package of the workspace
+fn test_workspace_package(
+    exercise_workspace: &ExerciseWorkspace,
+    package_name: &str,
+    compile_options: &CompileOptions,
+    filter: &TestFilter,
+    output: &mut ProgramOutput,
+) -> anyhow::Result<Vec<(String, TestStatus)>> {
+    let workspace = &exercise_workspace.workspace;
 
     let test_reports: Vec<Vec<(String, TestStatus)>> = workspace
         .into_iter()
-        .filter(|package| package.name.to_string() == "runner_crate")
+        .filter(|package| package.name.to_string() == package_name)
         .map(|package| {
             run_tests::<Bn254BlackBoxSolver>(
-                &workspace_file_manager,
-                &parsed_files,
+                &exercise_workspace.file_manager,
+                &exercise_workspace.parsed_files,
                 package,
-                pattern,
-                false,
-                None,
+                filter,
+                output,
+                exercise_workspace.oracle_resolver_url(),
                 Some(workspace.root_dir.clone()),
                 Some(package.name.to_string()),
-                &CompileOptions::default(),
+                compile_options,
             )
         })
         .collect::<Result<Vec<_>, _>>()?;
 
-    let test_report: Vec<(String, TestStatus)> = test_reports.into_iter().flatten().collect();
+    Ok(test_reports.into_iter().flatten().collect())
+}
+
+/// The error of a test run in which some tests failed, naming the first of them
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct TestsFailed {
+    pub first_failed: String,
+    message: &'static str,
+}
 
-    if test_report.iter().any(|(_, status)| status.failed()) {
-        anyhow::bail!("Some tests failed");
-    } else {
-        Ok("".into())
-    }
+/// The name of the test that failed first, if the error is the one of a test run
+pub fn failed_test(error: &anyhow::Error) -> Option<String> {
+    error
+        .downcast_ref::<TestsFailed>()
+        .map(|tests_failed| tests_failed.first_failed.clone())
+}
+
+// Runs tests on the testing crate with nargo
+pub fn nargo_test(exercise: &Exercise, filter: &TestFilter) -> anyhow::Result<String> {
+    with_output(exercise, |output| {
+        let exercise_workspace = load_exercise_workspace(exercise)?;
+
+        let test_report = test_workspace_package(
+            &exercise_workspace,
+            RUNNER_CRATE_NAME,
+            &compile_options(exercise),
+            filter,
+            output,
+        )?;
+        if test_report.is_empty() && *filter != TestFilter::Anything {
+            anyhow::bail!("No test of {exercise} has {filter}");
+        }
+
+        // Tests living in the grader module are the hidden ones added by `load_exercise_workspace`
+        let grader_prefix = format!("{GRADER_MODULE}::");
+        let (grader_report, learner_report): (Vec<_>, Vec<_>) = test_report
+            .iter()
+            .partition(|(test_name, _)| test_name.starts_with(&grader_prefix));
+        let first_failed = |report: &[&(String, TestStatus)]| {
+            report
+                .iter()
+                .find(|(_, status)| status.failed())
+                .map(|(test_name, _)| test_name.clone())
+        };
+        let grader_failed = grader_report
+            .iter()
+            .filter(|(_, status)| status.failed())
+            .count();
+
+        if exercise.grader.is_some() {
+            let grader_passed = grader_report.len() - grader_failed;
+            report!(
+                "Grader tests: {} passed, {} failed",
+                style(grader_passed).green(),
+                style(grader_failed).red()
+            );
+        }
+
+        if let Some(test_name) = first_failed(&learner_report) {
+            return Err(TestsFailed {
+                first_failed: test_name,
+                message: "Some tests failed",
+            }
+            .into());
+        } else if let Some(test_name) = first_failed(&grader_report) {
+            return Err(TestsFailed {
+                first_failed: test_name,
+                message: "Some grader tests failed",
+            }
+            .into());
+        }
+
+        // The tests don't compile the program, so compile it when there is a size budget
+        if exercise.max_opcodes.is_some() || exercise.max_brillig_opcodes.is_some() {
+            let compiled = compile_exercise_workspace(exercise, &exercise_workspace)?;
+            check_circuit_size(exercise, &compiled)?;
+        }
+        Ok(())
+    })
 }
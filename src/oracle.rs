@@ -0,0 +1,355 @@
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use acvm::{
+    acir::brillig::{ForeignCallParam, ForeignCallResult},
+    AcirField, FieldElement,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::exercise::{OracleMock, OracleValue};
+
+// The JSON-RPC method nargo resolves foreign calls with
+const RESOLVE_METHOD: &str = "resolve_foreign_call";
+
+// How often the listener and the idle connections check whether the server was stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// How long a client has to send a whole request once it started it, and to read the response
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A JSON-RPC server on localhost answering the oracle calls of an exercise from its mocks,
+/// in place of the external resolver of nargo. It stops when dropped.
+pub struct OracleServer {
+    url: String,
+    stopped: Arc<AtomicBool>,
+    listener_thread: Option<JoinHandle<()>>,
+}
+
+// A mock with its values parsed as fields
+struct ParsedMock {
+    name: String,
+    inputs: Option<Vec<ForeignCallParam<FieldElement>>>,
+    returns: Vec<ForeignCallParam<FieldElement>>,
+}
+
+// The foreign call sent by nargo, its other fields (session, package, ...) are ignored
+#[derive(Deserialize)]
+struct ForeignCallRequest {
+    function: String,
+    inputs: Vec<ForeignCallParam<FieldElement>>,
+}
+
+impl OracleServer {
+    pub fn start(mocks: &[OracleMock]) -> anyhow::Result<OracleServer> {
+        let mocks = Arc::new(
+            mocks
+                .iter()
+                .map(parse_mock)
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        );
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        // Accepts without blocking, so the listener notices when the server is stopped
+        listener.set_nonblocking(true)?;
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let listener_thread = {
+            let stopped = stopped.clone();
+            thread::spawn(move || {
+                let mut connection_threads = Vec::new();
+                while !stopped.load(Ordering::Relaxed) {
+                    let Ok((stream, _)) = listener.accept() else {
+                        thread::sleep(POLL_INTERVAL);
+                        continue;
+                    };
+                    let mocks = mocks.clone();
+                    let stopped = stopped.clone();
+                    connection_threads.push(thread::spawn(move || {
+                        // A broken connection only fails the call it was serving
+                        let _ = serve_connection(stream, &mocks, &stopped);
+                    }));
+                }
+                for connection_thread in connection_threads {
+                    let _ = connection_thread.join();
+                }
+            })
+        };
+
+        Ok(OracleServer {
+            url: format!("http://{address}"),
+            stopped,
+            listener_thread: Some(listener_thread),
+        })
+    }
+
+    /// The URL to give nargo as its foreign call resolver
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Drop for OracleServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(listener_thread) = self.listener_thread.take() {
+            let _ = listener_thread.join();
+        }
+    }
+}
+
+// The reading half of a connection. Once a request started, every read waits at most
+// until its deadline, so that the whole request must arrive in time.
+struct RequestStream {
+    stream: TcpStream,
+    deadline: Option<Instant>,
+}
+
+impl Read for RequestStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(deadline) = self.deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(ErrorKind::TimedOut.into());
+            }
+            self.stream.set_read_timeout(Some(remaining))?;
+        }
+        self.stream.read(buf)
+    }
+}
+
+// Answers the HTTP requests sent on the connection, until the client closes it
+// or the server is stopped
+fn serve_connection(
+    stream: TcpStream,
+    mocks: &[ParsedMock],
+    stopped: &AtomicBool,
+) -> std::io::Result<()> {
+    // The accepted stream may inherit the non-blocking mode of the listener
+    stream.set_nonblocking(false)?;
+    let mut reader = BufReader::new(RequestStream {
+        stream: stream.try_clone()?,
+        deadline: None,
+    });
+    let mut writer = stream;
+    writer.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    loop {
+        // Waits for the next request, checking in between whether the server was stopped
+        reader.get_mut().deadline = None;
+        writer.set_read_timeout(Some(POLL_INTERVAL))?;
+        loop {
+            match reader.fill_buf() {
+                Ok([]) => return Ok(()),
+                Ok(_) => break,
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    if stopped.load(Ordering::Relaxed) {
+                        return Ok(());
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        // A stalled client would otherwise keep the server from stopping
+        reader.get_mut().deadline = Some(Instant::now() + REQUEST_TIMEOUT);
+
+        let mut line = String::new();
+        // The request line, the method and the path don't matter
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+
+        let mut content_length = 0;
+        let mut keep_alive = true;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                let value = value.trim();
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.parse().unwrap_or(0);
+                } else if name.eq_ignore_ascii_case("connection") {
+                    keep_alive = !value.eq_ignore_ascii_case("close");
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        let response = respond(&body, mocks).to_string();
+        write!(
+            writer,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{response}",
+            response.len()
+        )?;
+        writer.flush()?;
+        if !keep_alive {
+            return Ok(());
+        }
+    }
+}
+
+// The JSON-RPC response to the request
+fn respond(body: &[u8], mocks: &[ParsedMock]) -> Value {
+    let request: Value = serde_json::from_slice(body).unwrap_or(Value::Null);
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    match resolve(&request, mocks) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(message) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32000, "message": message },
+        }),
+    }
+}
+
+// Finds the first mock of the called oracle accepting its inputs
+fn resolve(
+    request: &Value,
+    mocks: &[ParsedMock],
+) -> Result<ForeignCallResult<FieldElement>, String> {
+    let method = request.get("method").and_then(Value::as_str);
+    if method != Some(RESOLVE_METHOD) {
+        return Err(format!("Unknown method {method:?}"));
+    }
+    let params = request
+        .get("params")
+        .and_then(|params| params.get(0))
+        .cloned()
+        .ok_or("Missing foreign call")?;
+    let call: ForeignCallRequest =
+        serde_json::from_value(params).map_err(|err| format!("Invalid foreign call: {err}"))?;
+
+    mocks
+        .iter()
+        .find(|mock| {
+            mock.name == call.function
+                && mock
+                    .inputs
+                    .as_ref()
+                    .map_or(true, |inputs| *inputs == call.inputs)
+        })
+        .map(|mock| ForeignCallResult {
+            values: mock.returns.clone(),
+        })
+        .ok_or_else(|| {
+            format!(
+                "No mock of the oracle `{}` for the inputs {}",
+                call.function,
+                display_params(&call.inputs)
+            )
+        })
+}
+
+fn parse_mock(mock: &OracleMock) -> anyhow::Result<ParsedMock> {
+    let parse_values = |values: &[OracleValue]| {
+        values
+            .iter()
+            .map(|value| parse_value(&mock.name, value))
+            .collect::<anyhow::Result<Vec<_>>>()
+    };
+    Ok(ParsedMock {
+        name: mock.name.clone(),
+        inputs: mock.inputs.as_deref().map(parse_values).transpose()?,
+        returns: parse_values(&mock.returns)?,
+    })
+}
+
+fn parse_value(
+    oracle: &str,
+    value: &OracleValue,
+) -> anyhow::Result<ForeignCallParam<FieldElement>> {
+    let field = |value: &String| {
+        FieldElement::try_from_str(value).ok_or_else(|| {
+            anyhow::anyhow!("Invalid field `{value}` in the mock of the oracle `{oracle}`")
+        })
+    };
+    Ok(match value {
+        OracleValue::Single(value) => ForeignCallParam::Single(field(value)?),
+        OracleValue::Array(values) => {
+            ForeignCallParam::Array(values.iter().map(field).collect::<anyhow::Result<_>>()?)
+        }
+    })
+}
+
+fn display_params(params: &[ForeignCallParam<FieldElement>]) -> String {
+    let display_param = |param: &ForeignCallParam<FieldElement>| match param {
+        ForeignCallParam::Single(value) => value.to_string(),
+        ForeignCallParam::Array(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    format!(
+        "[{}]",
+        params
+            .iter()
+            .map(display_param)
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+#[test]
+fn test_mocks_resolve_by_name_and_inputs() {
+    let single = |value: &str| OracleValue::Single(value.to_string());
+    let mocks = [
+        OracleMock {
+            name: "get_sqrt".to_string(),
+            inputs: Some(vec![single("16")]),
+            returns: vec![single("4")],
+        },
+        // Without inputs, a mock answers any call of its oracle
+        OracleMock {
+            name: "get_sqrt".to_string(),
+            inputs: None,
+            returns: vec![single("0")],
+        },
+    ]
+    .iter()
+    .map(parse_mock)
+    .collect::<anyhow::Result<Vec<_>>>()
+    .unwrap();
+    let call = |function: &str, input: u128| {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": RESOLVE_METHOD,
+            "params": [{
+                "function": function,
+                "inputs": [ForeignCallParam::Single(FieldElement::from(input))],
+            }],
+        })
+    };
+    let returned = |value: u128| vec![ForeignCallParam::Single(FieldElement::from(value))];
+
+    assert_eq!(
+        resolve(&call("get_sqrt", 16), &mocks).unwrap().values,
+        returned(4)
+    );
+    assert_eq!(
+        resolve(&call("get_sqrt", 9), &mocks).unwrap().values,
+        returned(0)
+    );
+    assert!(resolve(&call("get_cube", 2), &mocks)
+        .unwrap_err()
+        .contains("No mock of the oracle `get_cube`"));
+}
@@ -0,0 +1,182 @@
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::exercise::Exercise;
+
+/// What becomes of the output printed by the circuits and the tests (`print`, `println`)
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputPolicy {
+    // Printed while the exercise runs
+    Show,
+    // Printed with the report of the exercise
+    Capture,
+    // Dropped
+    Suppress,
+}
+
+// The policy chosen for the whole session, over the ones of the exercises (0 when none was chosen)
+static SESSION_POLICY: AtomicU8 = AtomicU8::new(0);
+
+/// Sets the policy of every run from now on, as `--output` and the `output` command of watch do
+pub fn set_session_policy(policy: OutputPolicy) {
+    let value = match policy {
+        OutputPolicy::Show => 1,
+        OutputPolicy::Capture => 2,
+        OutputPolicy::Suppress => 3,
+    };
+    SESSION_POLICY.store(value, Ordering::Relaxed);
+}
+
+fn session_policy() -> Option<OutputPolicy> {
+    match SESSION_POLICY.load(Ordering::Relaxed) {
+        1 => Some(OutputPolicy::Show),
+        2 => Some(OutputPolicy::Capture),
+        3 => Some(OutputPolicy::Suppress),
+        _ => None,
+    }
+}
+
+/// The policy of a run of the exercise: the one of the session, else the one of the
+/// exercise, else the output is captured
+pub fn output_policy(exercise: &Exercise) -> OutputPolicy {
+    session_policy()
+        .or(exercise.output)
+        .unwrap_or(OutputPolicy::Capture)
+}
+
+// A report being captured on this thread
+struct CapturedReport {
+    text: String,
+    // Whether the text goes on to the enclosing report, or to the terminal, as well
+    forward: bool,
+}
+
+thread_local! {
+    // The reports of the runs being captured on this thread, the innermost last.
+    // A run is always captured by the thread that started it, even when it's
+    // picked up by a thread waiting on rayon jobs, since it then ends before the waiting one.
+    static CAPTURED_REPORTS: RefCell<Vec<CapturedReport>> = const { RefCell::new(Vec::new()) };
+}
+
+// Ends the capture it started, even when the run panics
+struct ReportCapture;
+
+impl ReportCapture {
+    fn start(forward: bool) -> ReportCapture {
+        CAPTURED_REPORTS.with_borrow_mut(|reports| {
+            reports.push(CapturedReport {
+                text: String::new(),
+                forward,
+            })
+        });
+        ReportCapture
+    }
+
+    fn end(self) -> String {
+        CAPTURED_REPORTS
+            .with_borrow_mut(|reports| {
+                reports
+                    .last_mut()
+                    .map(|report| std::mem::take(&mut report.text))
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for ReportCapture {
+    fn drop(&mut self) {
+        CAPTURED_REPORTS.with_borrow_mut(|reports| reports.pop());
+    }
+}
+
+/// Runs `run` and returns what it reported instead of printing it, so that runs happening
+/// at once (e.g. `verify --jobs`) can print their reports one after the other
+pub fn capture_report<T>(run: impl FnOnce() -> T) -> (T, String) {
+    let capture = ReportCapture::start(false);
+    let result = run();
+    (result, capture.end())
+}
+
+/// Runs `run` and returns what it reported, which is printed (or captured) all the same
+pub fn record_report<T>(run: impl FnOnce() -> T) -> (T, String) {
+    let capture = ReportCapture::start(true);
+    let result = run();
+    (result, capture.end())
+}
+
+/// Whether what is reported on this thread is being captured
+pub fn is_capturing_report() -> bool {
+    CAPTURED_REPORTS.with_borrow(|reports| reports.iter().any(|report| !report.forward))
+}
+
+/// Prints the text as part of the report of the current run, or adds it to the report
+/// being captured on this thread. Used through `report!`.
+pub fn report(text: &str) {
+    let printed = CAPTURED_REPORTS.with_borrow_mut(|reports| {
+        for report in reports.iter_mut().rev() {
+            report.text.push_str(text);
+            if !report.forward {
+                return false;
+            }
+        }
+        true
+    });
+    if printed {
+        print!("{text}");
+    }
+}
+
+impl FromStr for OutputPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "show" => Ok(OutputPolicy::Show),
+            "capture" => Ok(OutputPolicy::Capture),
+            "suppress" => Ok(OutputPolicy::Suppress),
+            _ => Err(format!(
+                "unknown output policy `{s}`, expected show, capture or suppress"
+            )),
+        }
+    }
+}
+
+impl Display for OutputPolicy {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let name = match self {
+            OutputPolicy::Show => "show",
+            OutputPolicy::Capture => "capture",
+            OutputPolicy::Suppress => "suppress",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[test]
+fn test_reports_are_captured_by_the_innermost_run() {
+    let (inner, outer) = capture_report(|| {
+        report("outer ");
+        let ((), inner) = capture_report(|| report("inner"));
+        report("again");
+        inner
+    });
+
+    assert_eq!(inner, "inner");
+    assert_eq!(outer, "outer again");
+    assert!(!is_capturing_report());
+}
+
+#[test]
+fn test_recorded_reports_go_on_to_the_enclosing_one() {
+    let (recorded, captured) = capture_report(|| {
+        let ((), recorded) = record_report(|| report("solved"));
+        recorded
+    });
+
+    assert_eq!(recorded, "solved");
+    assert_eq!(captured, "solved");
+}
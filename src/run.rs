@@ -1,26 +1,22 @@
 use std::process::Command;
 
 use crate::{
-    exercise::{BbVerifyOptions, Exercise, Mode},
-    utils,
+    exercise::{Exercise, Mode},
+    nargo::TestFilter,
+    utils::{self, Reporting},
 };
 
 // Invoke the rust compiler on the path of the given exercise,
 // and run the ensuing binary.
-// The verbose argument helps determine whether or not to show
-// the output from the test harnesses (if the mode of the exercise is test)
-pub fn run(exercise: &Exercise) -> Result<(), ()> {
-    let run_result = match &exercise.mode {
-        Mode::Build => utils::build_exercise(exercise)?,
-        Mode::Execute(str) => utils::execute_exercise(exercise, str.clone())?,
-        Mode::BbProve(str) => utils::bb_prove_exercise(exercise, str.clone())?,
-        Mode::BbVerify(BbVerifyOptions {toml_file,save_files}) => utils::bb_prove_verify_exercise(exercise, toml_file.clone(), *save_files)?,
-        Mode::Test => utils::test_exercise(exercise)?,
-        _ => {
-            eprintln!("Invalid mode for exercise: {}", exercise.name);
-            return Err(());
-        }
-    };
+// Test exercises only run the test functions selected by the filter.
+// Failures are reported as they happen, the error is returned for the caller to inspect
+pub fn run(exercise: &Exercise, test_filter: &TestFilter) -> anyhow::Result<()> {
+    if *test_filter != TestFilter::Anything && !matches!(exercise.mode, Mode::Test) {
+        eprintln!("{exercise} isn't a test exercise, its tests can't be selected");
+        anyhow::bail!("{exercise} isn't a test exercise");
+    }
+
+    let run_result = utils::run_exercise(exercise, test_filter, Reporting::Live)?;
     utils::print_exercise_output(run_result);
     utils::print_exercise_success(exercise);
     Ok(())
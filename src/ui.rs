@@ -54,3 +54,13 @@ macro_rules! progress {
         println!();
     }};
 }
+
+// Like `println!`, for the report of a run: captured when the run is
+macro_rules! report {
+    () => {{
+        crate::output::report("\n");
+    }};
+    ($($arg:tt)*) => {{
+        crate::output::report(&format!("{}\n", format!($($arg)*)));
+    }};
+}
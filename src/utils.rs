@@ -1,110 +1,319 @@
 use console::style;
 
-use crate::exercise::{Exercise, Mode, TomlFile};
+use crate::backend::check_proving_backend;
+use crate::exercise::{
+    BbVerifyOptions, CompileFailOptions, ContractOptions, ExecuteCase, ExecuteFailOptions,
+    Exercise, Mode, TomlFile,
+};
+use crate::nargo::TestFilter;
 // use crate::ui::progress;
 
+// Whether a run reports its progress and failures as it goes,
+// or leaves the reporting to its caller (e.g. when exercises run concurrently)
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Reporting {
+    Live,
+    Quiet,
+}
+
+impl Reporting {
+    fn is_live(self) -> bool {
+        self == Reporting::Live
+    }
+}
+
+// Runs the given Exercise according to its mode, holding the lock of its workspace
+// until the run, proofs included, is over.
+// Test exercises only run the test functions selected by the filter.
+pub fn run_exercise(
+    exercise: &Exercise,
+    test_filter: &TestFilter,
+    reporting: Reporting,
+) -> anyhow::Result<String> {
+    let _lock = exercise.lock_workspace().inspect_err(|error| {
+        if reporting.is_live() {
+            eprintln!("{error}");
+        }
+    })?;
+    match &exercise.mode {
+        Mode::Build => build_exercise(exercise, reporting),
+        Mode::Execute(toml_file) => execute_exercise(exercise, toml_file.clone(), reporting),
+        Mode::ExecuteCases(cases) => execute_cases_exercise(exercise, cases, reporting),
+        Mode::BbProve(toml_file) => bb_prove_exercise(exercise, toml_file.clone(), reporting),
+        Mode::BbVerify(options) => bb_prove_verify_exercise(exercise, options, reporting),
+        Mode::Test => test_exercise(exercise, test_filter, reporting),
+        Mode::CompileFail(options) => compile_fail_exercise(exercise, options, reporting),
+        Mode::ExecuteFail(options) => execute_fail_exercise(exercise, options, reporting),
+        Mode::Contract(options) => contract_exercise(exercise, options, reporting),
+        Mode::Library(_) => library_exercise(exercise, reporting),
+    }
+}
+
 // Build the given Exercise and return an object with information
 // about the state of the compilation
-pub fn build_exercise(exercise: &Exercise) -> Result<String, ()> {
-    progress!("Building {} exercise...", exercise);
+fn build_exercise(exercise: &Exercise, reporting: Reporting) -> anyhow::Result<String> {
+    if reporting.is_live() {
+        progress!("Building {} exercise...", exercise);
+    }
 
     let compilation_result = exercise.build();
 
-    if let Err(error) = compilation_result {
+    if let (Err(error), true) = (&compilation_result, reporting.is_live()) {
         eprintln!("{error}");
 
         warn!("Compiling of {} failed! Please try again.", exercise);
-        Err(())
-    } else {
-        Ok(compilation_result.unwrap())
     }
+    compilation_result
 }
 
 // Build the given Exercise and return an object with information
 // about the state of the compilation
-pub fn execute_exercise(exercise: &Exercise, prover_toml: TomlFile) -> Result<String, ()> {
-    progress!("Running {} exercise...", exercise);
+fn execute_exercise(
+    exercise: &Exercise,
+    prover_toml: TomlFile,
+    reporting: Reporting,
+) -> anyhow::Result<String> {
+    if reporting.is_live() {
+        progress!("Running {} exercise...", exercise);
+    }
 
     let compilation_result = exercise.execute(prover_toml);
 
-    if let Err(error) = compilation_result {
+    if let (Err(error), true) = (&compilation_result, reporting.is_live()) {
         eprintln!("{error}");
 
         warn!("Failed to run {}! Please try again.", exercise);
-        Err(())
-    } else {
-        Ok(compilation_result.unwrap())
     }
+    compilation_result
 }
 
+// Build the given Exercise as a contract and check its functions
+fn contract_exercise(
+    exercise: &Exercise,
+    options: &ContractOptions,
+    reporting: Reporting,
+) -> anyhow::Result<String> {
+    if reporting.is_live() {
+        progress!("Building {} contract...", exercise);
+    }
 
-pub fn bb_prove_exercise(exercise: &Exercise, prover_toml: TomlFile) -> Result<String, ()> {
-    progress!("Running {} exercise...", exercise);
+    let compilation_result = exercise.compile_contract(options);
 
-    let compilation_result = exercise.execute(prover_toml);
-    let proof_creation_result = exercise.create_proof();
+    if let (Err(error), true) = (&compilation_result, reporting.is_live()) {
+        eprintln!("{error}");
 
-    if let Err(error) = compilation_result {
+        warn!(
+            "Compiling of contract {} failed! Please try again.",
+            exercise
+        );
+    }
+    compilation_result
+}
+
+// Tests the library of the given Exercise through its consumer package
+fn library_exercise(exercise: &Exercise, reporting: Reporting) -> anyhow::Result<String> {
+    if reporting.is_live() {
+        progress!("Testing {} library...", exercise);
+    }
+
+    let compilation_result = exercise.test_library();
+
+    if let (Err(error), true) = (&compilation_result, reporting.is_live()) {
+        warn!(
+            "Testing of library {} failed! Please try again. See the output above ^",
+            exercise
+        );
+        println!("{error}");
+    }
+    compilation_result
+}
+
+// Execute the given Exercise once per case and report every case
+fn execute_cases_exercise(
+    exercise: &Exercise,
+    cases: &[ExecuteCase],
+    reporting: Reporting,
+) -> anyhow::Result<String> {
+    if reporting.is_live() {
+        progress!(
+            "Running {} exercise against {} cases...",
+            exercise,
+            cases.len()
+        );
+    }
+
+    let execution_result = exercise.execute_cases(cases);
+
+    if let (Err(error), true) = (&execution_result, reporting.is_live()) {
+        eprintln!("{error}");
+
+        warn!("Some cases of {} failed! Please try again.", exercise);
+    }
+    execution_result
+}
+
+// Build the given Exercise, which is expected to fail with a specific error
+fn compile_fail_exercise(
+    exercise: &Exercise,
+    options: &CompileFailOptions,
+    reporting: Reporting,
+) -> anyhow::Result<String> {
+    if reporting.is_live() {
+        progress!("Building {} exercise, expecting an error...", exercise);
+    }
+
+    let compilation_result = exercise.compile_fail(&options.expected);
+
+    if let (Err(error), true) = (&compilation_result, reporting.is_live()) {
         eprintln!("{error}");
 
-        warn!("Failed to execute {}! Please try again.", exercise);
-        Err(())
-    } else if let Err(error) = proof_creation_result {
+        warn!(
+            "{} did not fail the expected way! Please try again.",
+            exercise
+        );
+    }
+    compilation_result
+}
+
+// Execute the given Exercise, which is expected to fail with a specific message
+fn execute_fail_exercise(
+    exercise: &Exercise,
+    options: &ExecuteFailOptions,
+    reporting: Reporting,
+) -> anyhow::Result<String> {
+    if reporting.is_live() {
+        progress!("Running {} exercise, expecting a failure...", exercise);
+    }
+
+    let execution_result = exercise.execute_fail(options.toml_file.clone(), &options.expected);
+
+    if let (Err(error), true) = (&execution_result, reporting.is_live()) {
         eprintln!("{error}");
 
-        warn!("Compilation worked but failed to create proof with barretenberg for {}! Please try again.", exercise);
-        eprintln!("Are you sure you installed barretenberg properly ?");
-        Err(())
-        
-    } else {
-        Ok(compilation_result.unwrap())
+        warn!(
+            "{} did not fail the expected way! Please try again.",
+            exercise
+        );
     }
+    execution_result
 }
 
-pub fn bb_prove_verify_exercise(exercise: &Exercise, prover_toml: TomlFile, save_files: bool) -> Result<String, ()> {
-    progress!("Running {} exercise...", exercise);
+fn bb_prove_exercise(
+    exercise: &Exercise,
+    prover_toml: TomlFile,
+    reporting: Reporting,
+) -> anyhow::Result<String> {
+    if reporting.is_live() {
+        progress!("Running {} exercise...", exercise);
+    }
+    check_backend(exercise, reporting)?;
 
     let compilation_result = exercise.execute(prover_toml);
-    let verification_result = exercise.prove_verify_proof(save_files);
+    if let Err(error) = compilation_result {
+        if reporting.is_live() {
+            eprintln!("{error}");
+
+            warn!("Failed to execute {}! Please try again.", exercise);
+        }
+        return Err(error);
+    }
+
+    if let Err(error) = exercise.create_proof() {
+        if reporting.is_live() {
+            eprintln!("{error}");
+
+            warn!("Compilation worked but failed to create proof with barretenberg for {}! Please try again.", exercise);
+            eprintln!("Without bb, the exercise can still be checked with NOIRLINGS_BACKEND=mock");
+        }
+        return Err(error);
+    }
+    compilation_result
+}
+
+fn bb_prove_verify_exercise(
+    exercise: &Exercise,
+    options: &BbVerifyOptions,
+    reporting: Reporting,
+) -> anyhow::Result<String> {
+    if reporting.is_live() {
+        progress!("Running {} exercise...", exercise);
+    }
+    check_backend(exercise, reporting)?;
+
+    let prover_toml = exercise.bb_prover_toml(options).inspect_err(|error| {
+        if reporting.is_live() {
+            eprintln!("{error}");
+            warn!(
+                "Failed to derive the inputs of {}! Please try again.",
+                exercise
+            );
+        }
+    })?;
 
+    let compilation_result = exercise.execute(prover_toml);
     if let Err(error) = compilation_result {
-        eprintln!("{error}");
+        if reporting.is_live() {
+            eprintln!("{error}");
 
-        warn!("Failed to execute {}! Please try again.", exercise);
-        Err(())
-    } else if let Err(error) = verification_result {
-        eprintln!("{error}");
+            warn!("Failed to execute {}! Please try again.", exercise);
+        }
+        return Err(error);
+    }
+
+    if let Err(error) = exercise.prove_verify_proof(options.save_files) {
+        if reporting.is_live() {
+            eprintln!("{error}");
 
-        warn!("Compilation worked but failed to prove and verify with barretenberg backend for {}! Please try again.", exercise);
-        eprintln!("Are you sure you installed barretenberg properly ?");
-        Err(())
-        
-    } else {
-        Ok(compilation_result.unwrap())
+            warn!("Compilation worked but failed to prove and verify with barretenberg backend for {}! Please try again.", exercise);
+            eprintln!("Without bb, the exercise can still be checked with NOIRLINGS_BACKEND=mock");
+        }
+        return Err(error);
     }
+    compilation_result
+}
+
+// Checks the proving backend before the bb exercises compile and execute anything
+fn check_backend(exercise: &Exercise, reporting: Reporting) -> anyhow::Result<()> {
+    check_proving_backend().inspect_err(|error| {
+        if reporting.is_live() {
+            eprintln!("{error}");
+
+            warn!("No proving backend to run {}!", exercise);
+            eprintln!("Without bb, the exercise can still be checked with NOIRLINGS_BACKEND=mock");
+        }
+    })
 }
 
 // Tests the given Exercise and return an object with information
 // about the state of the tests
-pub fn test_exercise(exercise: &Exercise) -> Result<String, ()> {
-    progress!("Testing {} exercise...", exercise);
+fn test_exercise(
+    exercise: &Exercise,
+    filter: &TestFilter,
+    reporting: Reporting,
+) -> anyhow::Result<String> {
+    if reporting.is_live() {
+        progress!("Testing {} exercise...", exercise);
+    }
 
-    let compilation_result = exercise.test();
+    let compilation_result = exercise.test_matching(filter);
 
-    if let Some(error) = compilation_result.as_ref().err() {
+    if let (Err(error), true) = (&compilation_result, reporting.is_live()) {
         warn!(
             "Testing of {} failed! Please try again. See the output above ^",
             exercise
         );
         println!("{error}");
-        Err(())
-    } else {
-        Ok(compilation_result.unwrap())
     }
+    compilation_result
 }
 
 pub fn print_exercise_output(exercise_output: String) {
-    if exercise_output.len() > 0 {
+    if exercise_output.contains('\n') {
+        println!("    {}", style("Output").green().bold());
+        for line in exercise_output.lines() {
+            println!("    {line}");
+        }
+    } else if exercise_output.len() > 0 {
         println!("    {} {exercise_output}", style("Output").green().bold());
     }
 }
@@ -113,8 +322,13 @@ pub fn print_exercise_success(exercise: &Exercise) {
     match exercise.mode {
         Mode::Build => success!("Successfully built {}!", exercise),
         Mode::Execute(ref toml) => success!("Successfully ran {}!\n With inputs: {}", exercise, toml.to_string()),
+        Mode::ExecuteCases(ref cases) => success!("Successfully ran {} against all {} cases!", exercise, cases.len()),
         Mode::Test => success!("Successfully tested {}!", exercise),
         Mode::BbProve(ref toml) => success!("Successfully ran {} and created proof!\n With inputs: {}", exercise, toml.to_string()),
         Mode::BbVerify(ref options) => success!("Successfully ran {} and verified proof!\n With inputs: {}", exercise, options.toml_file.to_string()),
+        Mode::Contract(ref options) => success!("Successfully built contract {} from {}!", options.name, exercise),
+        Mode::Library(ref options) => success!("Successfully tested library {} from {}!", options.name, exercise),
+        Mode::CompileFail(ref options) => success!("{} failed to compile as expected with: {}", exercise, options.expected),
+        Mode::ExecuteFail(ref options) => success!("{} failed as expected with: {}\n With inputs: {}", exercise, options.expected, options.toml_file.to_string()),
     }
 }
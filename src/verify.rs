@@ -1,21 +1,26 @@
 use crate::{
     clear_screen,
-    exercise::{BbVerifyOptions, Exercise, Mode, State},
-    utils,
+    exercise::{Exercise, Mode, State},
+    nargo::TestFilter,
+    noir::failed_test,
+    output::capture_report,
+    utils::{self, Reporting},
 };
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
-use std::env;
+use rayon::prelude::*;
+use std::{collections::HashMap, env};
 
 // Verify that the provided container of Exercise objects
 // can be compiled and run without any failures.
 // Any such failures will be reported to the end user.
 // If the Exercise being verified is a test, the verbose boolean
 // determines whether or not the test harness outputs are displayed.
+// On failure, returns the exercise with the name of the test that failed first, if any.
 pub fn verify<'a>(
     exercises: impl IntoIterator<Item = &'a Exercise>,
     progress: (usize, usize),
-) -> Result<(), &'a Exercise> {
+) -> Result<(), (&'a Exercise, Option<String>)> {
     let (mut num_done, total) = progress;
     for exercise in exercises {
         clear_screen();
@@ -26,25 +31,10 @@ pub fn verify<'a>(
                 .progress_chars("#>-"),
         );
         bar.set_position(num_done as u64);
-        let exercise_result = {
-            let run_result = match &exercise.mode {
-                Mode::Build => utils::build_exercise(exercise),
-                Mode::Execute(str) => utils::execute_exercise(exercise, str.clone()),
-                Mode::BbProve(str) => utils::bb_prove_exercise(exercise, str.clone()),
-                Mode::BbVerify(BbVerifyOptions {toml_file,save_files}) => utils::bb_prove_verify_exercise(exercise, toml_file.clone(), *save_files),
-                Mode::Test => utils::test_exercise(exercise),
-                _ => {
-                    eprintln!("Invalid mode for exercise: {}", exercise.name);
-                    return Err(exercise);
-                }
-            };
-            match run_result {
-                Ok(run_state) => Ok(prompt_for_completion(exercise, Some(run_state))),
-                Err(_) => Err(()),
-            }
-        };
-        if !exercise_result.unwrap_or(false) {
-            return Err(exercise);
+        match utils::run_exercise(exercise, &TestFilter::Anything, Reporting::Live) {
+            Ok(run_state) if prompt_for_completion(exercise, Some(run_state)) => {}
+            Ok(_) => return Err((exercise, None)),
+            Err(error) => return Err((exercise, failed_test(&error))),
         }
         let percentage = num_done as f32 / total as f32 * 100.0;
         bar.set_message(format!("({percentage:.1} %)"));
@@ -55,6 +45,105 @@ pub fn verify<'a>(
     Ok(())
 }
 
+// Verifies the exercises concurrently on the global rayon pool.
+// An exercise only starts once the exercises it `requires` are over.
+// What each exercise reports is captured while it runs, and printed in curriculum order
+// once its wave is over.
+pub fn verify_parallel(exercises: &[Exercise]) -> Result<(), ()> {
+    let mut num_failed = 0;
+    for wave in dependency_waves(exercises) {
+        let wave_results: Vec<_> = wave
+            .par_iter()
+            .map(|&index| {
+                capture_report(|| {
+                    utils::run_exercise(&exercises[index], &TestFilter::Anything, Reporting::Quiet)
+                })
+            })
+            .collect();
+
+        for (index, (result, report)) in wave.into_iter().zip(wave_results) {
+            let exercise = &exercises[index];
+            match result {
+                Ok(output) if exercise.looks_done() => {
+                    success!("{} verified", exercise);
+                    print!("{report}");
+                    utils::print_exercise_output(output);
+                }
+                Ok(_) => {
+                    num_failed += 1;
+                    warn!("{} passes but is still marked as `I AM NOT DONE`", exercise);
+                    print!("{report}");
+                }
+                Err(error) => {
+                    num_failed += 1;
+                    warn!("{} failed!", exercise);
+                    print!("{report}");
+                    eprintln!("{error}");
+                }
+            }
+        }
+    }
+
+    println!(
+        "{} of {} exercises verified",
+        style(exercises.len() - num_failed).green().bold(),
+        exercises.len()
+    );
+    if num_failed > 0 {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+// Groups the exercises, by index, into waves that can run concurrently:
+// every exercise comes after the ones it requires, unknown requirements are ignored.
+fn dependency_waves(exercises: &[Exercise]) -> Vec<Vec<usize>> {
+    let indices: HashMap<&str, usize> = exercises
+        .iter()
+        .enumerate()
+        .map(|(index, exercise)| (exercise.name.as_str(), index))
+        .collect();
+
+    let mut depths: Vec<Option<usize>> = vec![None; exercises.len()];
+    let mut waves: Vec<Vec<usize>> = Vec::new();
+    for index in 0..exercises.len() {
+        let depth = exercise_depth(index, exercises, &indices, &mut depths, &mut Vec::new());
+        if waves.len() <= depth {
+            waves.resize_with(depth + 1, Vec::new);
+        }
+        waves[depth].push(index);
+    }
+    waves
+}
+
+fn exercise_depth(
+    index: usize,
+    exercises: &[Exercise],
+    indices: &HashMap<&str, usize>,
+    depths: &mut [Option<usize>],
+    visiting: &mut Vec<usize>,
+) -> usize {
+    if let Some(depth) = depths[index] {
+        return depth;
+    }
+    // A requirement cycle is broken at the exercise closing it
+    if visiting.contains(&index) {
+        return 0;
+    }
+    visiting.push(index);
+    let depth = exercises[index]
+        .requires
+        .iter()
+        .filter_map(|name| indices.get(name.as_str()))
+        .map(|&required| exercise_depth(required, exercises, indices, depths, visiting) + 1)
+        .max()
+        .unwrap_or(0);
+    visiting.pop();
+    depths[index] = Some(depth);
+    depth
+}
+
 fn prompt_for_completion(exercise: &Exercise, prompt_output: Option<String>) -> bool {
     let context = match exercise.state() {
         State::Done => return true,
@@ -73,9 +162,14 @@ fn prompt_for_completion(exercise: &Exercise, prompt_output: Option<String>) ->
     let success_msg = match exercise.mode {
         Mode::Build => "The code is compiling!",
         Mode::Execute(_) => "The code is compiling based on the witnesses!",
+        Mode::ExecuteCases(_) => "The code is compiling and every case behaves as expected!",
         Mode::BbProve(_) => "The code is compiling and a bb proof has been created!",
         Mode::Test => "The code is compiling, and the tests pass!",
         Mode::BbVerify(_) => "The code is compiling and the bb proof has been verified!",
+        Mode::CompileFail(_) => "The code fails to compile, just as expected!",
+        Mode::ExecuteFail(_) => "The circuit rejects the inputs, just as expected!",
+        Mode::Contract(_) => "The contract is compiling with the expected functions!",
+        Mode::Library(_) => "The library is compiling, and the consumer tests pass!",
         // Mode::Clippy => clippy_success_msg,
     };
 